@@ -1,3 +1,5 @@
+#![allow(clippy::redundant_closure)]
+
 use ugly_global::*;
 
 global_vars! {
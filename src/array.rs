@@ -0,0 +1,173 @@
+use crate::sync::{Global, GlobalGuard};
+
+/// A fixed-size array of `Global`s, addressed by index. Each
+/// element is locked and initialized separately.
+pub struct GlobalArray<T, const N: usize>([Global<T>; N]);
+
+impl<T: 'static, const N: usize> GlobalArray<T, N> {
+    /// Only used to fill the array in `new()`, which needs a
+    /// constant to repeat.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const UNINIT: Global<T> = Global::new();
+
+    /// Get a new array of uninitialized globals.
+    pub const fn new() -> Self {
+        GlobalArray([Self::UNINIT; N])
+    }
+
+    /// Wrap an existing array of globals, such as one built
+    /// with an inline `const` block, to get the indexed
+    /// accessors of `GlobalArray`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// static POOL: GlobalArray<String, 4> =
+    ///     GlobalArray::from_array([const { Global::new() }; 4]);
+    ///
+    /// POOL.init(3, "spare".to_string());
+    /// assert_eq!(*POOL.fetch(3), "spare");
+    /// ```
+    pub const fn from_array(globals: [Global<T>; N]) -> Self {
+        GlobalArray(globals)
+    }
+
+    /// The number of globals in the array.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Report whether the array is empty.
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// The global at index `i`, giving access to all of the
+    /// `Global` methods.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     array SLOTS: [Vec<usize>; 4];
+    /// }
+    ///
+    /// SLOTS.get(1).set(vec![1]);
+    /// assert_eq!(SLOTS.get(1).take(), [1]);
+    /// assert!(!SLOTS.get(2).is_initialized());
+    /// ```
+    #[track_caller]
+    pub fn get(&self, i: usize) -> &Global<T> {
+        match self.0.get(i) {
+            Some(g) => g,
+            None => panic!("global array index {} out of bounds for length {}", i, N),
+        }
+    }
+
+    /// Iterate over the globals in the array.
+    pub fn iter(&self) -> core::slice::Iter<'_, Global<T>> {
+        self.0.iter()
+    }
+
+    /// Report whether the global at index `i` has been
+    /// initialized.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds.
+    #[track_caller]
+    pub fn is_initialized(&self, i: usize) -> bool {
+        self.get(i).is_initialized()
+    }
+
+    /// Lock the global at index `i` and acquire the object
+    /// used to access it.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds. See also
+    /// `Global::fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     array SLOTS: [usize; 8];
+    /// }
+    ///
+    /// for i in 0..SLOTS.len() {
+    ///     SLOTS.init(i, i);
+    /// }
+    /// let threads: Vec<_> = (0..SLOTS.len())
+    ///     .map(|i| std::thread::spawn(move || *SLOTS.fetch(i) *= 2))
+    ///     .collect();
+    /// for t in threads {
+    ///     t.join().unwrap();
+    /// }
+    /// assert_eq!(*SLOTS.fetch(7), 14);
+    /// ```
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     array SLOTS: [usize; 8];
+    /// }
+    ///
+    /// // Panics with "global array index 8 out of bounds for length 8".
+    /// SLOTS.fetch(8);
+    /// ```
+    #[track_caller]
+    pub fn fetch(&self, i: usize) -> GlobalGuard<'_, T> {
+        self.get(i).fetch()
+    }
+
+    /// Lock the global at index `i` and acquire the object
+    /// used to access it, without panicking if it is not
+    /// available. See `Global::try_fetch()`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds.
+    #[track_caller]
+    #[must_use = "the global may not have been fetched"]
+    pub fn try_fetch(&self, i: usize) -> Option<GlobalGuard<'_, T>> {
+        self.get(i).try_fetch()
+    }
+
+    /// Initialize the global at index `i` to contain an
+    /// initial value.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds. See also
+    /// `Global::init()`.
+    #[track_caller]
+    pub fn init(&self, i: usize, v: T) {
+        self.get(i).init(v);
+    }
+
+    /// Initialize the global at index `i` to contain an
+    /// initial value, without panicking if it is already
+    /// initialized. See `Global::try_init()`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `i` is out of bounds.
+    #[track_caller]
+    pub fn try_init(&self, i: usize, v: T) -> Result<(), T> {
+        self.get(i).try_init(v)
+    }
+}
+
+impl<T: 'static, const N: usize> Default for GlobalArray<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,240 @@
+use core::sync::atomic::Ordering::SeqCst;
+
+use crate::lock::OnceCell;
+
+/// Types with a corresponding atomic type, which can be
+/// stored in an `AtomicGlobal`.
+pub trait AtomicValue: Copy + 'static {
+    /// The atomic type used to hold values of this type.
+    type Atomic: Send + Sync;
+
+    #[doc(hidden)]
+    fn new_atomic(v: Self) -> Self::Atomic;
+    #[doc(hidden)]
+    fn load(a: &Self::Atomic) -> Self;
+    #[doc(hidden)]
+    fn store(a: &Self::Atomic, v: Self);
+    #[doc(hidden)]
+    fn swap(a: &Self::Atomic, v: Self) -> Self;
+}
+
+/// Integer types whose atomic type supports arithmetic.
+pub trait AtomicInteger: AtomicValue {
+    #[doc(hidden)]
+    fn fetch_add(a: &Self::Atomic, v: Self) -> Self;
+    #[doc(hidden)]
+    fn fetch_sub(a: &Self::Atomic, v: Self) -> Self;
+}
+
+macro_rules! atomic_value {
+    ($t:ty, $a:ty) => {
+        impl AtomicValue for $t {
+            type Atomic = $a;
+
+            fn new_atomic(v: Self) -> $a {
+                <$a>::new(v)
+            }
+
+            fn load(a: &$a) -> Self {
+                a.load(SeqCst)
+            }
+
+            fn store(a: &$a, v: Self) {
+                a.store(v, SeqCst)
+            }
+
+            fn swap(a: &$a, v: Self) -> Self {
+                a.swap(v, SeqCst)
+            }
+        }
+    };
+}
+
+macro_rules! atomic_integer {
+    ($($(#[$m:meta])* $t:ty, $a:ty;)*) => {
+        $(
+            $(#[$m])*
+            atomic_value!($t, $a);
+
+            $(#[$m])*
+            impl AtomicInteger for $t {
+                fn fetch_add(a: &$a, v: Self) -> Self {
+                    a.fetch_add(v, SeqCst)
+                }
+
+                fn fetch_sub(a: &$a, v: Self) -> Self {
+                    a.fetch_sub(v, SeqCst)
+                }
+            }
+        )*
+    };
+}
+
+atomic_value!(bool, core::sync::atomic::AtomicBool);
+
+atomic_integer! {
+    #[cfg(target_has_atomic = "8")]
+    u8, core::sync::atomic::AtomicU8;
+    #[cfg(target_has_atomic = "8")]
+    i8, core::sync::atomic::AtomicI8;
+    #[cfg(target_has_atomic = "16")]
+    u16, core::sync::atomic::AtomicU16;
+    #[cfg(target_has_atomic = "16")]
+    i16, core::sync::atomic::AtomicI16;
+    #[cfg(target_has_atomic = "32")]
+    u32, core::sync::atomic::AtomicU32;
+    #[cfg(target_has_atomic = "32")]
+    i32, core::sync::atomic::AtomicI32;
+    #[cfg(target_has_atomic = "64")]
+    u64, core::sync::atomic::AtomicU64;
+    #[cfg(target_has_atomic = "64")]
+    i64, core::sync::atomic::AtomicI64;
+    usize, core::sync::atomic::AtomicUsize;
+    isize, core::sync::atomic::AtomicIsize;
+}
+
+/// Global type for scalars such as counters and flags,
+/// backed by an atomic rather than a mutex. There is no
+/// guard: values are read and written whole, without
+/// locking. All accesses are `SeqCst`.
+pub struct AtomicGlobal<T: AtomicValue>(OnceCell<T::Atomic>);
+
+impl<T: AtomicValue> AtomicGlobal<T> {
+    /// Get a new, uninitialized atomic global.
+    pub const fn new() -> Self {
+        AtomicGlobal(OnceCell::new())
+    }
+
+    #[track_caller]
+    fn atomic(&self) -> &T::Atomic {
+        self.0.get().expect("global uninitialized")
+    }
+
+    /// Report whether the global has been initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value.  See `init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on initialization failure; for example on an attempt
+    /// to reinitialize a variable.
+    #[track_caller]
+    pub fn init(&self, v: T) {
+        if self.try_init(v).is_err() {
+            panic!("initialization failed");
+        }
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value, without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the global
+    /// has already been initialized.
+    pub fn try_init(&self, v: T) -> Result<(), T> {
+        let mut v = Some(v);
+        self.0.get_or_init(|| T::new_atomic(v.take().unwrap()));
+        match v {
+            None => Ok(()),
+            Some(v) => Err(v),
+        }
+    }
+
+    /// Read the value of a global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     atomic READY: bool;
+    /// }
+    ///
+    /// init!(READY = false);
+    /// READY.store(true);
+    /// assert!(READY.load());
+    /// ```
+    #[track_caller]
+    pub fn load(&self) -> T {
+        T::load(self.atomic())
+    }
+
+    /// Write the value of a global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    #[track_caller]
+    pub fn store(&self, v: T) {
+        T::store(self.atomic(), v)
+    }
+
+    /// Write the value of a global, returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    #[track_caller]
+    pub fn swap(&self, v: T) -> T {
+        T::swap(self.atomic(), v)
+    }
+
+    /// Add to the value of a global, wrapping on overflow, and
+    /// return the old value.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     atomic COUNT: u64;
+    /// }
+    ///
+    /// init!(COUNT = 0);
+    /// let threads: Vec<_> = (0..4)
+    ///     .map(|_| std::thread::spawn(|| COUNT.fetch_add(1)))
+    ///     .collect();
+    /// for t in threads {
+    ///     t.join().unwrap();
+    /// }
+    /// assert_eq!(COUNT.load(), 4);
+    /// ```
+    #[track_caller]
+    pub fn fetch_add(&self, v: T) -> T
+    where
+        T: AtomicInteger,
+    {
+        T::fetch_add(self.atomic(), v)
+    }
+
+    /// Subtract from the value of a global, wrapping on
+    /// overflow, and return the old value.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    #[track_caller]
+    pub fn fetch_sub(&self, v: T) -> T
+    where
+        T: AtomicInteger,
+    {
+        T::fetch_sub(self.atomic(), v)
+    }
+}
+
+impl<T: AtomicValue> Default for AtomicGlobal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
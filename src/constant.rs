@@ -0,0 +1,81 @@
+use crate::lock::OnceCell;
+
+/// Global type for `Copy` constants that may occasionally need
+/// to be overridden at runtime, such as configuration limits.
+/// The global starts with a `const` value, which `init()` may
+/// replace once. Reads copy the value out without locking.
+/// See `global_const!()` for the macro normally used to
+/// declare these.
+pub struct ConstGlobal<T: Copy> {
+    default: T,
+    value: OnceCell<T>,
+}
+
+impl<T: Copy + 'static> ConstGlobal<T> {
+    /// Get a new global with the given value.
+    pub const fn new(default: T) -> Self {
+        ConstGlobal {
+            default,
+            value: OnceCell::new(),
+        }
+    }
+
+    /// Report whether the global's value has been overridden
+    /// with `init()`.
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+
+    /// Read the value of a global.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_const! {
+    ///     MAX: u32 = 1024;
+    /// }
+    ///
+    /// assert_eq!(MAX.get(), 1024);
+    /// MAX.init(16);
+    /// assert_eq!(MAX.get(), 16);
+    /// ```
+    pub fn get(&self) -> T {
+        match self.value.get() {
+            Some(v) => *v,
+            None => self.default,
+        }
+    }
+
+    /// The value the global was declared with, even if it has
+    /// since been overridden.
+    pub fn default_value(&self) -> T {
+        self.default
+    }
+
+    /// Override the value of a global. See `init!()` for the
+    /// macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the value has already been overridden.
+    #[track_caller]
+    pub fn init(&self, v: T) {
+        if self.try_init(v).is_err() {
+            panic!("initialization failed");
+        }
+    }
+
+    /// Override the value of a global, without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the value
+    /// has already been overridden.
+    pub fn try_init(&self, v: T) -> Result<(), T> {
+        let mut v = Some(v);
+        self.value.get_or_init(|| v.take().unwrap());
+        match v {
+            None => Ok(()),
+            Some(v) => Err(v),
+        }
+    }
+}
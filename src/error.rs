@@ -0,0 +1,61 @@
+use core::fmt;
+
+/// Reasons a global could not be accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub enum GlobalError {
+    /// The global has not yet been initialized.
+    Uninitialized,
+    /// A thread panicked while holding the global, poisoning
+    /// its lock.
+    Poisoned,
+}
+
+impl fmt::Display for GlobalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlobalError::Uninitialized => f.write_str("global uninitialized"),
+            GlobalError::Poisoned => f.write_str("global lock poisoned"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for GlobalError {}
+
+/// A global's lock could not be acquired in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError;
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out waiting for global lock")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TimeoutError {}
+
+/// A global could not be initialized from an environment
+/// variable, produced by `Global::try_init_from_env()`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError<E> {
+    /// The variable is not set, or is not valid Unicode.
+    Var(std::env::VarError),
+    /// The variable's value could not be parsed.
+    Parse(E),
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Display> fmt::Display for EnvError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Var(e) => write!(f, "environment variable unusable: {}", e),
+            EnvError::Parse(e) => write!(f, "environment variable unparseable: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for EnvError<E> {}
@@ -0,0 +1,84 @@
+use crate::lock::OnceCell;
+
+/// Global type for values that never change once
+/// initialized, such as loaded configuration. Since the value
+/// is never mutated, reads are plain shared references with
+/// no guard and no locking.
+pub struct FrozenGlobal<T>(OnceCell<T>);
+
+impl<T: 'static> FrozenGlobal<T> {
+    /// Get a new, uninitialized frozen global.
+    pub const fn new() -> Self {
+        FrozenGlobal(OnceCell::new())
+    }
+
+    /// Report whether the global has been initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Get a reference to the value of a global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// struct Config { verbose: bool };
+    /// global_vars! {
+    ///     frozen CONFIG: Config;
+    /// }
+    ///
+    /// init!(CONFIG = Config { verbose: true });
+    /// let a = CONFIG.get();
+    /// let b = CONFIG.get();
+    /// assert!(a.verbose && b.verbose);
+    /// ```
+    #[track_caller]
+    pub fn get(&self) -> &T {
+        self.0.get().expect("global uninitialized")
+    }
+
+    /// Get a reference to the value of a global, or `None` if
+    /// it has not yet been initialized.
+    pub fn try_get(&self) -> Option<&T> {
+        self.0.get()
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value.  See `init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on initialization failure; for example on an attempt
+    /// to reinitialize a variable.
+    #[track_caller]
+    pub fn init(&self, v: T) {
+        if self.try_init(v).is_err() {
+            panic!("initialization failed");
+        }
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value, without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the global
+    /// has already been initialized.
+    pub fn try_init(&self, v: T) -> Result<(), T> {
+        let mut v = Some(v);
+        self.0.get_or_init(|| v.take().unwrap());
+        match v {
+            None => Ok(()),
+            Some(v) => Err(v),
+        }
+    }
+}
+
+impl<T: 'static> Default for FrozenGlobal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
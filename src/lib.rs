@@ -2,17 +2,174 @@
 //! this crate. Really slow and gross, but better than
 //! nothing.
 //!
-//! Currently only thread-safe sync global variables are
-//! provided.  Future enhancements may include thread_local
-//! variables, if the `std` ones are ever upgraded to have a
-//! guard type instead of just being usable in a closure.
+//! Thread-safe sync global variables are provided: `Global`
+//! is backed by a `Mutex`, and `RwGlobal` is backed by an
+//! `RwLock` for globals that are read far more often than
+//! they are written. `LocalGlobal` provides thread-local
+//! global variables, with a separate value for each thread.
+//! `AtomicGlobal` holds a scalar such as a counter or flag
+//! in an atomic, avoiding locking entirely. `GlobalArray`
+//! holds a fixed number of `Global`s addressed by index.
+//! `FrozenGlobal` holds a value that never changes after
+//! initialization, and hands out plain references to it
+//! without locking. `ConstGlobal` holds a `Copy` constant
+//! that can be overridden once at runtime, and is read
+//! without locking. `BoxGlobal` is a `Global` holding a
+//! boxed trait object, for plugin-style globals chosen at
+//! initialization.
+//!
+//! With the `parking_lot` feature, `Global` uses
+//! `parking_lot::Mutex` instead of `std::sync::Mutex`. The
+//! API is unchanged, but locks are never poisoned and
+//! `fetch_timeout()` uses a true timed lock.
+//!
+//! With the `fair` feature, which implies `parking_lot`, each
+//! global is unlocked fairly: the lock passes directly to the
+//! longest-waiting thread, so that under contention globals
+//! are granted in roughly FIFO order at some cost in
+//! throughput.
+//!
+//! With the `metrics` feature, each `Global` counts its lock
+//! acquisitions and the time spent waiting for them, as
+//! reported by `Global::stats()`.
+//!
+//! With the `lock_order` feature, debug builds check that
+//! globals are always locked in a consistent order. Each
+//! time a global is locked while others are held, the order
+//! is recorded; locking globals in an order that contradicts
+//! one seen before, which could deadlock, panics. Only
+//! blocking locks are checked: `try_fetch_now()`,
+//! `fetch_timeout()` and the like cannot deadlock, and only
+//! record the order when they succeed. Release builds do no
+//! checking. The check is meant for `static`
+//! globals: globals are told apart by address, so a `Global`
+//! made at runtime and locked alongside others may be
+//! confused with one previously at the same address.
+//!
+//! With the `trace_init` feature, each initialization of a
+//! `Global` is logged at debug level through the `log`
+//! crate, with the name of the global, the initializing
+//! thread, and the location of the call. This covers
+//! `init()` and `init!()`, `try_init()`,
+//! `set_if_uninitialized()`, `get_or_init()`, a `set()` of
+//! an uninitialized global, and the running of a lazy
+//! initializer. The feature is spelled with an underscore,
+//! like the crate's other features, not as `trace-init`.
+//!
+//! With the `serde` feature, a `Global` can be serialized,
+//! and set from serialized data with
+//! `Global::deserialize_into()`.
+//!
+//! With the `async` feature, `Global::fetch_async()` locks a
+//! global from an async function without blocking the
+//! executor, and `Global::poll_fetch()` does the same for
+//! hand-written futures.
+//!
+//! The `once_cell` feature is on by default, and uses the
+//! `once_cell` crate to hold each global's lock. Turning it
+//! off while keeping the `std` feature uses
+//! `std::sync::OnceLock` instead, dropping the dependency.
+//! The API is unchanged.
+//!
+//! The `std` feature is on by default. Without it the crate
+//! is `no_std`, and `Global` is built on `spin::Mutex` and
+//! `spin::Once`; the `spin` feature must then be turned on,
+//! so that `std` users never build the `spin` crate.
+//! `RwGlobal`, `LocalGlobal` and `fetch_timeout()` are not
+//! available in this configuration.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "spin")))]
+compile_error!("ugly_global needs either the `std` feature or the `spin` feature");
 
-use std::sync::{Mutex, MutexGuard};
+mod array;
+mod atomic;
+mod constant;
+mod error;
+mod frozen;
+#[cfg(feature = "std")]
+mod local;
+mod lock;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(all(feature = "lock_order", debug_assertions))]
+mod order;
+#[cfg(feature = "testing")]
+mod registry;
+#[cfg(feature = "std")]
+mod rwsync;
+#[cfg(feature = "serde")]
+mod serialize;
+mod sync;
+mod wakers;
 
-use once_cell::sync::OnceCell;
+pub use array::GlobalArray;
+pub use atomic::{AtomicGlobal, AtomicInteger, AtomicValue};
+pub use constant::ConstGlobal;
+#[cfg(feature = "std")]
+pub use error::EnvError;
+pub use error::{GlobalError, TimeoutError};
+pub use frozen::FrozenGlobal;
+#[cfg(feature = "testing")]
+#[doc(hidden)]
+pub use inventory;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use local::{LocalCell, LocalState};
+#[cfg(feature = "std")]
+pub use local::{LocalGlobal, LocalGuard};
+#[cfg(feature = "metrics")]
+pub use metrics::GlobalStats;
+#[cfg(all(feature = "testing", feature = "std"))]
+pub use registry::install_crash_dumper;
+#[cfg(feature = "testing")]
+pub use registry::{count_registered, registered, reset_all, GlobalInfo};
+#[cfg(feature = "testing")]
+#[doc(hidden)]
+pub use registry::{AnyGlobal, DebugProbe, Registered, ViaDebug, ViaNoDebug};
+#[cfg(feature = "std")]
+pub use rwsync::RwGlobal;
+#[cfg(feature = "std")]
+pub use sync::BoxGlobal;
+#[cfg(feature = "async")]
+pub use sync::FetchFuture;
+pub use sync::{Global, GlobalGuard, MappedGuard, OverrideGuard};
 
-/// Declare mutable global variables. Use uppercase
-/// variable names to avoid compiler warnings.
+/// Declare mutable global variables. Uppercase names are
+/// conventional, but lowercase names are allowed without
+/// compiler warnings.
+///
+/// Each declaration produces a `Global` by default. Prefix a
+/// declaration with `rwsync` to produce an `RwGlobal`, with
+/// `local` to produce a `LocalGlobal`, with `atomic` to
+/// produce an `AtomicGlobal`, with `frozen` to produce a
+/// `FrozenGlobal`, or with `array` to produce a `GlobalArray`
+/// from an array type such as `[T; 8]`, instead. The prefix
+/// `sync` asks for a `Global` explicitly. Declarations of
+/// different kinds may be mixed in one invocation. A
+/// declaration may start with a visibility such as `pub` or
+/// `pub(crate)`, which is given to the generated `static`.
+/// A declaration whose type is a trait object, such as
+/// `dyn Plugin + Send`, produces a `BoxGlobal` holding the
+/// boxed trait object. The trait must be named by a plain
+/// path, without generic arguments.
+/// Doc comments and other attributes, such as `#[cfg(...)]`,
+/// may precede a declaration and are likewise passed on.
+///
+/// Declarations may be grouped into a module with
+/// `mod name { ... }`, which generates the module. Items of
+/// the enclosing module are in scope inside it, but the
+/// globals need a visibility such as `pub` to be used from
+/// outside it.
+///
+/// With the `testing` feature, each `Global` declared is
+/// registered for `reset_all()` and `install_crash_dumper()`.
+///
+/// A `Global` declaration may be given an initializer with
+/// `= expr`. The expression is evaluated on first access
+/// rather than at program start, so it need not be `const`,
+/// and no `init!()` is needed.
 ///
 /// # Examples
 ///
@@ -21,16 +178,222 @@ use once_cell::sync::OnceCell;
 /// struct S { x: usize, y: usize };
 /// global_vars! {
 ///     X: S;
+///     /// A global that does not exist.
+///     #[cfg(any())]
+///     W: S;
+/// }
+///
+/// mod m {
+///     ugly_global::global_vars! {
+///         pub Y: usize;
+///         pub(crate) Z: usize;
+///     }
 /// }
+/// m::Y.init(0);
+/// m::Z.init(0);
+///
+/// global_vars! {
+///     V: Vec<usize> = vec![1, 2, 3];
+/// }
+/// assert_eq!(V.fetch().len(), 3);
+///
+/// global_vars! {
+///     mod config {
+///         pub VERBOSE: bool = false;
+///         pub(crate) NAME: String;
+///     }
+/// }
+/// assert!(!*config::VERBOSE.fetch());
+/// config::NAME.init(String::new());
 /// ```
 #[macro_export]
 macro_rules! global_vars {
-    ($($x:ident : $t:ty ;)*) => {
-        $(static $x: $crate::Global<$t> =
-            $crate::Global::new();)*
+    () => {};
+    ($(#[$m:meta])* $vis:vis mod $name:ident { $($body:tt)* } $($rest:tt)*) => {
+        $(#[$m])*
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+            $crate::global_vars! { $($body)* }
+        }
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis sync $x:ident : $($rest:tt)*) => {
+        $crate::global_vars! { $(#[$m])* $vis $x : $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis rwsync $x:ident : $t:ty ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::RwGlobal<$t> =
+            $crate::RwGlobal::new();
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis atomic $x:ident : $t:ty ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::AtomicGlobal<$t> =
+            $crate::AtomicGlobal::new();
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis array $x:ident : [$t:ty; $n:expr] ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::GlobalArray<$t, $n> =
+            $crate::GlobalArray::new();
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis frozen $x:ident : $t:ty ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::FrozenGlobal<$t> =
+            $crate::FrozenGlobal::new();
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis local $x:ident : $t:ty ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::LocalGlobal<$t> = {
+            ::std::thread_local! {
+                static CELL: $crate::LocalCell<$t> =
+                    $crate::LocalCell::new();
+            }
+            $crate::LocalGlobal::new(CELL)
+        };
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis $x:ident : dyn $($tr:ident)::+ $(+ $more:tt)* = $e:expr ; $($rest:tt)*) => {
+        $crate::global_vars! {
+            $(#[$m])* $vis $x : ::std::boxed::Box<dyn $($tr)::+ $(+ $more)*> = $e;
+            $($rest)*
+        }
+    };
+    ($(#[$m:meta])* $vis:vis $x:ident : dyn $($tr:ident)::+ $(+ $more:tt)* ; $($rest:tt)*) => {
+        $crate::global_vars! {
+            $(#[$m])* $vis $x : ::std::boxed::Box<dyn $($tr)::+ $(+ $more)*>;
+            $($rest)*
+        }
+    };
+    ($(#[$m:meta])* $vis:vis $x:ident : $t:ty = $e:expr ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::Global<$t> =
+            $crate::Global::new_lazy_named(|| $e, stringify!($x));
+        $crate::__register_global! { $(#[$m])* $x }
+        $crate::global_vars! { $($rest)* }
+    };
+    ($(#[$m:meta])* $vis:vis $x:ident : $t:ty ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::Global<$t> =
+            $crate::Global::new_named(stringify!($x));
+        $crate::__register_global! { $(#[$m])* $x }
+        $crate::global_vars! { $($rest)* }
     };
 }
 
+/// Declare mutable global variables, each initialized with
+/// the value of an expression on first access. This is the
+/// same as a `global_vars!` declaration with an initializer,
+/// but every declaration must have one.
+///
+/// The expression is evaluated while the global is locked,
+/// so it runs only once even if several threads race to
+/// access the global.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use ugly_global::*;
+///
+/// static LOADS: AtomicUsize = AtomicUsize::new(0);
+///
+/// fn load_config() -> Vec<String> {
+///     LOADS.fetch_add(1, Ordering::SeqCst);
+///     vec!["verbose".to_string()]
+/// }
+///
+/// lazy_global! {
+///     CONFIG: Vec<String> = load_config();
+/// }
+///
+/// let threads: Vec<_> = (0..4)
+///     .map(|_| std::thread::spawn(|| CONFIG.fetch().len()))
+///     .collect();
+/// for t in threads {
+///     assert_eq!(t.join().unwrap(), 1);
+/// }
+/// assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+/// ```
+#[macro_export]
+macro_rules! lazy_global {
+    () => {};
+    ($(#[$m:meta])* $vis:vis $x:ident : $t:ty = $e:expr ; $($rest:tt)*) => {
+        $crate::global_vars! { $(#[$m])* $vis $x : $t = $e; }
+        $crate::lazy_global! { $($rest)* }
+    };
+}
+
+/// Declare `Copy` constants that can be overridden once at
+/// runtime, as `ConstGlobal`s. The initializer must be a
+/// constant expression. Reads copy the value without
+/// locking.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_const! {
+///     /// Largest request accepted, in bytes.
+///     pub MAX_REQUEST: usize = 1 << 20;
+///     VERBOSE: bool = false;
+/// }
+///
+/// assert_eq!(MAX_REQUEST.get(), 1 << 20);
+/// init!(VERBOSE = true);
+/// assert!(VERBOSE.get());
+/// ```
+#[macro_export]
+macro_rules! global_const {
+    () => {};
+    ($(#[$m:meta])* $vis:vis $x:ident : $t:ty = $e:expr ; $($rest:tt)*) => {
+        $(#[$m])*
+        #[allow(non_upper_case_globals)]
+        $vis static $x: $crate::ConstGlobal<$t> = $crate::ConstGlobal::new($e);
+        $crate::global_const! { $($rest)* }
+    };
+}
+
+/// Register a `Global` declared by `global_vars!` for
+/// `reset_all()`. Only for use by `global_vars!`.
+#[cfg(feature = "testing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_global {
+    ($(#[$m:meta])* $x:ident) => {
+        $(#[$m])*
+        const _: () = {
+            $crate::inventory::submit! {
+                $crate::Registered {
+                    global: &$x,
+                    debug: || {
+                        use $crate::{ViaDebug, ViaNoDebug};
+                        (&&$crate::DebugProbe(&$x)).debug()
+                    },
+                }
+            }
+        };
+    };
+}
+
+/// Without the `testing` feature there is no registry.
+#[cfg(not(feature = "testing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_global {
+    ($(#[$m:meta])* $x:ident) => {};
+}
+
 /// Declare a local identifier containing a mutable reference
 /// to a global variable. The reference will be statically invalid
 /// at the end of the scope in which `fetch!()` is invoked.
@@ -64,8 +427,278 @@ macro_rules! fetch {
     };
 }
 
+/// Lock a global variable for the duration of a block, with
+/// a local identifier containing a mutable reference to it as
+/// in `fetch!()`. The global is unlocked at the end of the
+/// block, which makes it clear how long the lock is held.
+/// The value of the block is the value of the macro.
+///
+/// # Panics
+///
+/// See `fetch()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// struct S { u: usize };
+/// global_vars! {
+///     X: S = S { u: 0 };
+/// }
+///
+/// let u = scope!(s = X {
+///     s.u += 1;
+///     s.u
+/// });
+/// assert_eq!(u, 1);
+/// assert!(X.try_fetch_now().is_some());
+/// ```
+#[macro_export]
+macro_rules! scope {
+    ($y:ident = $x:ident $body:block) => {{
+        let mut $y = $x.fetch();
+        $body
+    }};
+}
+
+/// Declare a local identifier containing a mutable reference
+/// to a field of a global variable, as with `fetch!()` and
+/// `GlobalGuard::map()`. The whole global stays locked until
+/// the end of the scope. The field may be a path of several
+/// fields, such as `X.a.b`.
+///
+/// # Panics
+///
+/// See `fetch()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// struct Stats { hits: usize, sizes: (usize, usize) };
+/// global_vars! {
+///     STATS: Stats = Stats { hits: 0, sizes: (0, 0) };
+/// }
+///
+/// {
+///     fetch_map!(hits = STATS.hits);
+///     *hits += 1;
+/// }
+/// {
+///     fetch_map!(largest = STATS.sizes.1);
+///     *largest = 10;
+/// }
+/// assert_eq!(STATS.fetch().hits, 1);
+/// assert_eq!(STATS.fetch().sizes, (0, 10));
+/// ```
+#[macro_export]
+macro_rules! fetch_map {
+    ($y:ident = $x:ident $(. $f:tt)+) => {
+        let mut $y = $x.fetch().map(|v| &v $(. $f)+, |v| &mut v $(. $f)+);
+    };
+}
+
+/// Declare local identifiers containing mutable references
+/// to several global variables at once, as with `fetch!()`.
+///
+/// The globals are always locked in `id()` order,
+/// regardless of the order in which they are given, so two
+/// `fetch_all!()`s of the same globals cannot deadlock
+/// against each other.
+///
+/// # Panics
+///
+/// See `fetch()`. Will also panic if the same global is named
+/// twice, rather than deadlocking, with or without `std`:
+///
+/// ```should_panic
+/// use ugly_global::*;
+/// global_vars! {
+///     A: usize = 1;
+/// }
+///
+/// fetch_all!(a = A, b = A);
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     A: usize = 1;
+///     B: String = String::new();
+/// }
+///
+/// fn f() {
+///     fetch_all!(a = A, b = B);
+///     b.push_str(&a.to_string());
+///     *a += 1;
+/// }
+///
+/// fn g() {
+///     fetch_all!(b = B, a = A);
+///     b.push_str(&a.to_string());
+/// }
+///
+/// let t = std::thread::spawn(f);
+/// g();
+/// t.join().unwrap();
+/// assert_eq!(B.fetch().len(), 2);
+/// ```
+#[macro_export]
+macro_rules! fetch_all {
+    ($($y:ident = $x:ident),+ $(,)?) => {
+        $(let mut $y = None;)+
+        {
+            let mut ids = [$($x.id()),+];
+            ids.sort_unstable();
+            if ids.windows(2).any(|w| w[0] == w[1]) {
+                panic!("global named twice in fetch_all!()");
+            }
+            for id in ids.iter() {
+                $(
+                    if *id == $x.id() && $y.is_none() {
+                        $y = Some($x.fetch());
+                    }
+                )+
+            }
+        }
+        $(let mut $y = $y.unwrap();)+
+    };
+}
+
+/// Clone the values of several global variables at once,
+/// producing a tuple of the clones. All of the globals are
+/// locked together, as by `fetch_all!()`, so the snapshot is
+/// consistent: no other thread can change one of them
+/// between the cloning of another.
+///
+/// `Global` is not itself `Clone`, since a copy would be a
+/// separate global. Clone values instead, as here or with
+/// `get_cloned()`.
+///
+/// # Panics
+///
+/// See `fetch_all!()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     SENT: usize = 0;
+///     RECEIVED: usize = 0;
+///     LOG: Vec<&'static str> = Vec::new();
+/// }
+///
+/// fn transfer() {
+///     fetch_all!(sent = SENT, received = RECEIVED, log = LOG);
+///     *sent += 1;
+///     *received += 1;
+///     log.push("transfer");
+/// }
+///
+/// let t = std::thread::spawn(|| (0..100).for_each(|_| transfer()));
+/// for _ in 0..100 {
+///     let (sent, received, log) = snapshot_all!(SENT, RECEIVED, LOG);
+///     assert_eq!(sent, received);
+///     assert_eq!(log.len(), sent);
+/// }
+/// t.join().unwrap();
+/// ```
+#[macro_export]
+macro_rules! snapshot_all {
+    ($($x:ident),+ $(,)?) => {
+        $crate::snapshot_all!(@bind [] $($x),+)
+    };
+    // Each recursion introduces a distinct hygienic `guard`
+    // binding for one global.
+    (@bind [$($done:tt)*] $x:ident $(, $rest:ident)*) => {
+        $crate::snapshot_all!(@bind [$($done)* ($x guard)] $($rest),*)
+    };
+    (@bind [$(($x:ident $g:ident))+]) => {{
+        $crate::fetch_all!($($g = $x),+);
+        ($(::core::clone::Clone::clone(&*$g),)+)
+    }};
+}
+
+/// Attempt to lock a global variable, producing `None` if
+/// the global is not available.
+///
+/// See `try_fetch()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// struct S { u: usize };
+/// global_vars! {
+///     X: S;
+/// }
+///
+/// assert!(try_fetch!(X).is_none());
+/// init!(X = S { u: 0 });
+/// if let Some(mut s) = try_fetch!(X) {
+///     s.u += 1;
+/// }
+/// assert_eq!(try_fetch!(X).unwrap().u, 1);
+/// ```
+#[macro_export]
+macro_rules! try_fetch {
+    ($x:ident) => {
+        $x.try_fetch()
+    };
+}
+
+/// Declare a local identifier containing a mutable reference
+/// to a global variable, as with `fetch!()`, or return early
+/// if the global is not available.
+///
+/// `fetch_try!(y = X)` returns `Default::default()` from the
+/// enclosing function when `try_fetch()` fails.
+/// `fetch_try!(y = X else e)` evaluates `e` instead, which
+/// must diverge, for example with `return` or `continue`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     NAMES: Vec<String>;
+/// }
+///
+/// fn count() -> usize {
+///     fetch_try!(names = NAMES);
+///     names.len()
+/// }
+///
+/// fn first() -> Option<String> {
+///     fetch_try!(names = NAMES else return None);
+///     names.first().cloned()
+/// }
+///
+/// assert_eq!(count(), 0);
+/// assert_eq!(first(), None);
+/// init!(NAMES = vec!["a".to_string()]);
+/// assert_eq!(count(), 1);
+/// assert_eq!(first().unwrap(), "a");
+/// ```
+#[macro_export]
+macro_rules! fetch_try {
+    ($y:ident = $x:ident) => {
+        $crate::fetch_try!($y = $x else return ::core::default::Default::default());
+    };
+    ($y:ident = $x:ident else $e:expr) => {
+        let mut $y = match $x.try_fetch() {
+            Some(guard) => guard,
+            None => $e,
+        };
+    };
+}
+
 /// Initialize a global variable. Must be called before
-/// first access.
+/// first access. Several globals may be initialized at once
+/// by separating their assignments with commas.
 ///
 /// # Panics
 ///
@@ -89,49 +722,251 @@ macro_rules! fetch {
 ///     init!(X = S { u: 0 });
 /// }
 /// ```
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: usize;
+///     Y: String;
+/// }
+///
+/// init!(X = 1, Y = "y".to_string());
+/// assert_eq!(*X.fetch(), 1);
+/// assert_eq!(*Y.fetch(), "y");
+/// ```
+///
+/// Either form is an expression:
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     P: usize;
+///     Q: usize;
+/// }
+///
+/// let setup = || init!(P = 1, Q = 2);
+/// setup();
+/// assert_eq!(P.get_copy() + Q.get_copy(), 3);
+/// ```
 #[macro_export]
 macro_rules! init {
     ($x:ident = $v:expr) => {
         $x.init($v)
     };
+    ($($x:ident = $v:expr),+ $(,)?) => {{
+        $($x.init($v);)+
+    }};
 }
 
-/// Global type.
-pub struct Global<T>(OnceCell<Mutex<T>>);
-
-impl<T: 'static> Global<T> {
-    /// Global `OnceCell` function --- used to get a new
-    /// `OnceCell` with `once_cell` in scope.
-    pub const fn new() -> Self {
-        Global(OnceCell::new())
-    }
-
-    /// Lock a global and acquire the object used to access it. See
-    /// `fetch!()` for the macro normally used here.
-    ///
-    /// # Panics
-    ///
-    /// Will panic if the global has not yet been initialized.
-    /// Will panic if the underlying mutex gets poisoned (should
-    /// not happen).
-    pub fn fetch(&self) -> MutexGuard<'_, T> {
-        self.0
-            .get()
-            .expect("global uninitialized")
-            .lock()
-            .expect("global lock poisoned")
-    }
-
-    /// Initialize a global reference to contain an initial
-    /// value.  See `init!()` for the macro normally used here.
-    ///
-    /// # Panics
-    ///
-    /// Will panic on initialization failure; for example on an attempt
-    /// to reinitialize a variable.
-    pub fn init(&self, v: T) {
-        if self.0.set(Mutex::new(v)).is_err() {
-            panic!("initialization failed");
-        }
-    }
+/// Initialize a global variable with the result of calling
+/// a function. Must be called before first access.
+///
+/// # Panics
+///
+/// See `init()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     CONFIG: Vec<String>;
+/// }
+///
+/// fn build_config() -> Vec<String> {
+///     vec!["verbose".to_string()]
+/// }
+///
+/// init_from!(CONFIG = build_config);
+/// assert_eq!(CONFIG.fetch().len(), 1);
+/// ```
+#[macro_export]
+macro_rules! init_from {
+    ($x:ident = $f:expr) => {
+        $x.init_from($f)
+    };
+}
+
+/// Initialize a global variable with its type's default
+/// value. Must be called before first access.
+///
+/// # Panics
+///
+/// See `init()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: Vec<usize>;
+/// }
+///
+/// init_default!(X);
+/// assert!(X.fetch().is_empty());
+/// ```
+#[macro_export]
+macro_rules! init_default {
+    ($x:ident) => {
+        $x.init_default()
+    };
+}
+
+/// Initialize a global variable from an environment
+/// variable, parsed with `FromStr`. Must be called before
+/// first access.
+///
+/// `env_init!(X = "VAR", default = v)` uses `v` if the
+/// variable is unset or does not parse. `env_init!(X =
+/// "VAR")` instead returns the error as an `EnvError`,
+/// leaving the global uninitialized.
+///
+/// # Panics
+///
+/// See `init()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     PORT: u16;
+///     THREADS: usize;
+/// }
+///
+/// std::env::remove_var("EXAMPLE_APP_PORT");
+/// env_init!(PORT = "EXAMPLE_APP_PORT", default = 8080);
+/// assert_eq!(*PORT.fetch(), 8080);
+///
+/// std::env::set_var("EXAMPLE_APP_THREADS", "4");
+/// env_init!(THREADS = "EXAMPLE_APP_THREADS").unwrap();
+/// assert_eq!(*THREADS.fetch(), 4);
+/// ```
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! env_init {
+    ($x:ident = $var:expr, default = $d:expr $(,)?) => {
+        $x.init_from_env_or($var, $d)
+    };
+    ($x:ident = $var:expr) => {
+        $x.try_init_from_env($var)
+    };
+}
+
+/// Lock a global variable, apply a mutation to it, and
+/// release the lock.
+///
+/// # Panics
+///
+/// See `update()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// struct S { count: usize };
+/// global_vars! {
+///     X: S = S { count: 0 };
+/// }
+///
+/// update!(X, |s| s.count += 1);
+/// X.update(|s| s.count += 1);
+/// assert_eq!(X.fetch().count, 2);
+/// ```
+#[macro_export]
+macro_rules! update {
+    ($x:ident, $f:expr) => {
+        $x.update($f)
+    };
+}
+
+/// Take everything out of a collection-typed global, leaving
+/// it empty, and return it to be processed without holding
+/// the lock. This works for any type with a `Default`, but
+/// suits queues of work in `Vec`s, `VecDeque`s and maps.
+///
+/// # Panics
+///
+/// See `take()`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::VecDeque;
+/// use ugly_global::*;
+/// global_vars! {
+///     JOBS: VecDeque<u32> = VecDeque::new();
+/// }
+///
+/// JOBS.fetch().extend([1, 2, 3]);
+/// let mut done = Vec::new();
+/// for job in drain!(JOBS) {
+///     // More jobs can be queued meanwhile.
+///     JOBS.fetch().push_back(job * 10);
+///     done.push(job);
+/// }
+/// assert_eq!(done, [1, 2, 3]);
+/// assert_eq!(*JOBS.fetch(), [10, 20, 30]);
+/// ```
+#[macro_export]
+macro_rules! drain {
+    ($x:ident) => {
+        $x.take()
+    };
+}
+
+/// Temporarily replace the value of a global variable,
+/// restoring the old value at the end of the scope holding
+/// the returned guard.
+///
+/// # Panics
+///
+/// See `scoped_override()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     LIMIT: usize = 10;
+/// }
+///
+/// {
+///     let _limit = scoped_override!(LIMIT = 1);
+///     assert_eq!(*LIMIT.fetch(), 1);
+/// }
+/// assert_eq!(*LIMIT.fetch(), 10);
+/// ```
+#[macro_export]
+macro_rules! scoped_override {
+    ($x:ident = $v:expr) => {
+        $x.scoped_override($v)
+    };
+}
+
+/// Reset every `Global` declared with `global_vars!`. Only
+/// available with the `testing` feature.
+///
+/// # Panics
+///
+/// See `reset_all()`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: usize;
+/// }
+///
+/// init!(X = 1);
+/// reset_all!();
+/// init!(X = 2);
+/// ```
+#[cfg(feature = "testing")]
+#[macro_export]
+macro_rules! reset_all {
+    () => {
+        $crate::reset_all()
+    };
 }
@@ -0,0 +1,168 @@
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::thread::LocalKey;
+
+/// Per-thread state of a `LocalGlobal`.
+#[doc(hidden)]
+pub enum LocalState<T> {
+    Uninit,
+    Value(T),
+    Fetched,
+}
+
+/// Thread-local storage for a `LocalGlobal`. Only for use
+/// by `global_vars!`.
+#[doc(hidden)]
+pub struct LocalCell<T>(Cell<LocalState<T>>);
+
+impl<T> LocalCell<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        LocalCell(Cell::new(LocalState::Uninit))
+    }
+}
+
+/// Thread-local global type. Each thread has its own
+/// independently-initialized value.
+///
+/// `std`'s thread-local variables can only be accessed
+/// inside a closure passed to `LocalKey::with()`, so there
+/// is no reference to the value that can outlive the
+/// closure. Instead, `fetch()` moves the value out of the
+/// thread-local slot into the returned `LocalGuard`, and
+/// dropping the guard moves it back. While the guard is live
+/// the slot is marked as fetched, so a second `fetch()` on
+/// the same thread panics rather than seeing an
+/// uninitialized global.
+pub struct LocalGlobal<T: 'static>(LocalKey<LocalCell<T>>);
+
+/// Guard type giving access to the value of a fetched
+/// `LocalGlobal`. The value is returned to the thread-local
+/// slot when the guard is dropped. Guards cannot be sent to
+/// other threads.
+pub struct LocalGuard<T: 'static> {
+    global: &'static LocalGlobal<T>,
+    value: Option<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<T: 'static> Deref for LocalGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("global already returned")
+    }
+}
+
+impl<T: 'static> DerefMut for LocalGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("global already returned")
+    }
+}
+
+impl<T: 'static> Drop for LocalGuard<T> {
+    fn drop(&mut self) {
+        if let Some(v) = self.value.take() {
+            // The slot may already be gone if this guard is
+            // being dropped during thread teardown.
+            let _ = self.global.0.try_with(|c| c.0.set(LocalState::Value(v)));
+        }
+    }
+}
+
+impl<T: 'static> LocalGlobal<T> {
+    /// Wrap the thread-local storage declared by
+    /// `global_vars!`.
+    #[doc(hidden)]
+    pub const fn new(key: LocalKey<LocalCell<T>>) -> Self {
+        LocalGlobal(key)
+    }
+
+    /// Report whether the global has been initialized on the
+    /// current thread.
+    pub fn is_initialized(&'static self) -> bool {
+        self.0.with(|c| {
+            let state = c.0.replace(LocalState::Uninit);
+            let initialized = !matches!(state, LocalState::Uninit);
+            c.0.set(state);
+            initialized
+        })
+    }
+
+    /// Acquire the current thread's value of a global. See
+    /// `fetch!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized
+    /// on the current thread. Will panic if the current thread
+    /// already holds a guard for the global.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     local X: usize;
+    /// }
+    ///
+    /// init!(X = 1);
+    /// *X.fetch() += 1;
+    /// std::thread::spawn(|| {
+    ///     init!(X = 10);
+    ///     assert_eq!(*X.fetch(), 10);
+    /// })
+    /// .join()
+    /// .unwrap();
+    /// assert_eq!(*X.fetch(), 2);
+    /// ```
+    #[track_caller]
+    pub fn fetch(&'static self) -> LocalGuard<T> {
+        let v = match self.0.with(|c| c.0.replace(LocalState::Fetched)) {
+            LocalState::Value(v) => v,
+            LocalState::Uninit => {
+                self.0.with(|c| c.0.set(LocalState::Uninit));
+                panic!("global uninitialized");
+            }
+            LocalState::Fetched => panic!("global already fetched"),
+        };
+        LocalGuard {
+            global: self,
+            value: Some(v),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Initialize the current thread's value of a global.
+    /// See `init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on initialization failure; for example on an attempt
+    /// to reinitialize a variable on the same thread.
+    #[track_caller]
+    pub fn init(&'static self, v: T) {
+        if self.try_init(v).is_err() {
+            panic!("initialization failed");
+        }
+    }
+
+    /// Initialize the current thread's value of a global,
+    /// without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the global
+    /// has already been initialized on the current thread.
+    pub fn try_init(&'static self, v: T) -> Result<(), T> {
+        self.0.with(|c| match c.0.replace(LocalState::Uninit) {
+            LocalState::Uninit => {
+                c.0.set(LocalState::Value(v));
+                Ok(())
+            }
+            state => {
+                c.0.set(state);
+                Err(v)
+            }
+        })
+    }
+}
@@ -0,0 +1,584 @@
+//! Synchronization backends. By default the mutex is
+//! `std::sync::Mutex`; with the `parking_lot` feature it is
+//! `parking_lot::Mutex`, and without the `std` feature it is
+//! `spin::Mutex`. The latter two are wrapped to present the
+//! same interface as the standard mutex, never reporting
+//! poisoning. With the `fair` feature the `parking_lot`
+//! guard is wrapped so that dropping it unlocks fairly.
+//! Likewise the once-cell is `once_cell`'s with
+//! the `once_cell` feature, `std::sync::OnceLock` with `std`
+//! but not `once_cell`, and is built on `spin::Once` without
+//! `std`.
+//!
+//! For testing under `--cfg loom`, the standard mutex and
+//! condition variable are replaced by `loom`'s models, the
+//! once-cell and the `Holder` atomic are modelled with
+//! `loom` mutexes, so that `loom` explores the interleavings
+//! of initialization and of re-entrance checks as well as of
+//! locking.
+
+#[cfg(feature = "std")]
+pub(crate) use std::sync::{PoisonError, TryLockError};
+
+#[cfg(all(feature = "std", loom))]
+pub(crate) use loom_once::OnceCell;
+#[cfg(not(feature = "std"))]
+pub(crate) use no_std::{OnceCell, PoisonError, TryLockError};
+#[cfg(all(feature = "once_cell", not(loom)))]
+pub(crate) use once_cell::sync::OnceCell;
+#[cfg(all(feature = "std", not(feature = "once_cell"), not(loom)))]
+pub(crate) use std::sync::OnceLock as OnceCell;
+
+#[cfg(all(feature = "std", not(feature = "parking_lot"), not(loom)))]
+pub(crate) use std::sync::MutexGuard;
+#[cfg(all(feature = "std", not(feature = "parking_lot"), not(loom)))]
+pub(crate) use std_mutex::{Condvar, Mutex};
+
+#[cfg(all(feature = "std", not(feature = "parking_lot"), loom))]
+pub(crate) use loom::sync::MutexGuard;
+#[cfg(all(feature = "std", not(feature = "parking_lot"), loom))]
+pub(crate) use loom_mutex::{Condvar, Mutex};
+
+#[cfg(feature = "parking_lot")]
+pub(crate) use parking_lot_mutex::{Condvar, Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use spin_mutex::{Mutex, MutexGuard};
+
+#[cfg(all(feature = "std", not(feature = "parking_lot"), not(loom)))]
+mod std_mutex {
+    use std::sync::{LockResult, MutexGuard, TryLockError, TryLockResult};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    pub(crate) struct Mutex<T>(std::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(v: T) -> Self {
+            Mutex(std::sync::Mutex::new(v))
+        }
+
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            self.0.lock()
+        }
+
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock()
+        }
+
+        /// The standard mutex has no timed lock, so poll with
+        /// `try_lock()` until the deadline, yielding between
+        /// attempts.
+        pub(crate) fn try_lock_for(&self, dur: Duration) -> TryLockResult<MutexGuard<'_, T>> {
+            let deadline = Instant::now() + dur;
+            loop {
+                match self.0.try_lock() {
+                    Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                        thread::yield_now();
+                    }
+                    r => return r,
+                }
+            }
+        }
+
+        pub(crate) fn get_mut(&mut self) -> LockResult<&mut T> {
+            self.0.get_mut()
+        }
+
+        pub(crate) fn into_inner(self) -> LockResult<T> {
+            self.0.into_inner()
+        }
+
+        pub(crate) fn is_poisoned(&self) -> bool {
+            self.0.is_poisoned()
+        }
+
+        pub(crate) fn clear_poison(&self) {
+            self.0.clear_poison();
+        }
+    }
+
+    pub(crate) struct Condvar(std::sync::Condvar);
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar(std::sync::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+        ) -> LockResult<MutexGuard<'a, T>> {
+            self.0.wait(guard)
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+/// Under `--cfg loom`, the standard backend is replaced by
+/// `loom`'s model of it, so that tests can explore every
+/// interleaving of threads.
+#[cfg(all(feature = "std", not(feature = "parking_lot"), loom))]
+mod loom_mutex {
+    use std::sync::{LockResult, TryLockResult};
+    use std::time::Duration;
+
+    use loom::sync::MutexGuard;
+
+    pub(crate) struct Mutex<T>(loom::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(v: T) -> Self {
+            Mutex(loom::sync::Mutex::new(v))
+        }
+
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            self.0.lock()
+        }
+
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock()
+        }
+
+        /// `loom` does not model time, so a timed lock is a
+        /// single attempt.
+        pub(crate) fn try_lock_for(&self, _dur: Duration) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock()
+        }
+
+        pub(crate) fn get_mut(&mut self) -> LockResult<&mut T> {
+            self.0.get_mut()
+        }
+
+        pub(crate) fn into_inner(self) -> LockResult<T> {
+            self.0.into_inner()
+        }
+
+        pub(crate) fn is_poisoned(&self) -> bool {
+            false
+        }
+
+        pub(crate) fn clear_poison(&self) {}
+    }
+
+    /// `loom`'s condition variable cannot be built in a
+    /// `const`, so it is made on first use.
+    pub(crate) struct Condvar(super::OnceCell<loom::sync::Condvar>);
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar(super::OnceCell::new())
+        }
+
+        fn get(&self) -> &loom::sync::Condvar {
+            self.0.get_or_init(loom::sync::Condvar::new)
+        }
+
+        pub(crate) fn wait<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+        ) -> LockResult<MutexGuard<'a, T>> {
+            self.get().wait(guard)
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.get().notify_all();
+        }
+    }
+}
+
+/// `loom` has no once-cell, so under `--cfg loom` one is
+/// modelled as a `OnceLock` whose every access is made under a
+/// `loom` mutex. The mutex is made on first use, since `loom`
+/// types cannot be built in a `const`.
+#[cfg(all(feature = "std", loom))]
+mod loom_once {
+    use std::sync::OnceLock;
+
+    use loom::sync::{Mutex, MutexGuard};
+
+    pub(crate) struct OnceCell<T> {
+        value: OnceLock<T>,
+        gate: OnceLock<Mutex<()>>,
+    }
+
+    impl<T> OnceCell<T> {
+        pub(crate) const fn new() -> Self {
+            OnceCell {
+                value: OnceLock::new(),
+                gate: OnceLock::new(),
+            }
+        }
+
+        fn enter(&self) -> MutexGuard<'_, ()> {
+            self.gate
+                .get_or_init(|| Mutex::new(()))
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+        }
+
+        pub(crate) fn get(&self) -> Option<&T> {
+            let _gate = self.enter();
+            self.value.get()
+        }
+
+        pub(crate) fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+            let _gate = self.enter();
+            self.value.get_or_init(f)
+        }
+
+        pub(crate) fn set(&self, v: T) -> Result<(), T> {
+            let _gate = self.enter();
+            self.value.set(v)
+        }
+
+        pub(crate) fn get_mut(&mut self) -> Option<&mut T> {
+            self.value.get_mut()
+        }
+
+        pub(crate) fn into_inner(self) -> Option<T> {
+            self.value.into_inner()
+        }
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+mod parking_lot_mutex {
+    #[cfg(feature = "fair")]
+    use core::ops::{Deref, DerefMut};
+    use std::sync::{LockResult, TryLockError, TryLockResult};
+    use std::time::Duration;
+
+    #[cfg(not(feature = "fair"))]
+    pub(crate) use parking_lot::MutexGuard;
+
+    #[cfg(not(feature = "fair"))]
+    fn wrap<T>(guard: MutexGuard<'_, T>) -> MutexGuard<'_, T> {
+        guard
+    }
+
+    /// Guard that hands the lock directly to the next waiting
+    /// thread, if any, when dropped, so that the mutex is
+    /// granted in roughly FIFO order.
+    #[cfg(feature = "fair")]
+    pub(crate) struct MutexGuard<'a, T>(Option<parking_lot::MutexGuard<'a, T>>);
+
+    #[cfg(feature = "fair")]
+    fn wrap<T>(guard: parking_lot::MutexGuard<'_, T>) -> MutexGuard<'_, T> {
+        MutexGuard(Some(guard))
+    }
+
+    #[cfg(feature = "fair")]
+    impl<'a, T> MutexGuard<'a, T> {
+        fn inner(&mut self) -> &mut parking_lot::MutexGuard<'a, T> {
+            self.0.as_mut().expect("guard already unlocked")
+        }
+    }
+
+    #[cfg(feature = "fair")]
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            self.0.as_ref().expect("guard already unlocked")
+        }
+    }
+
+    #[cfg(feature = "fair")]
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            self.inner()
+        }
+    }
+
+    #[cfg(feature = "fair")]
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            if let Some(guard) = self.0.take() {
+                parking_lot::MutexGuard::unlock_fair(guard);
+            }
+        }
+    }
+
+    pub(crate) struct Mutex<T>(parking_lot::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(v: T) -> Self {
+            Mutex(parking_lot::Mutex::new(v))
+        }
+
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            Ok(wrap(self.0.lock()))
+        }
+
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock().map(wrap).ok_or(TryLockError::WouldBlock)
+        }
+
+        pub(crate) fn try_lock_for(&self, dur: Duration) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0
+                .try_lock_for(dur)
+                .map(wrap)
+                .ok_or(TryLockError::WouldBlock)
+        }
+
+        pub(crate) fn get_mut(&mut self) -> LockResult<&mut T> {
+            Ok(self.0.get_mut())
+        }
+
+        pub(crate) fn into_inner(self) -> LockResult<T> {
+            Ok(self.0.into_inner())
+        }
+
+        pub(crate) fn is_poisoned(&self) -> bool {
+            false
+        }
+
+        pub(crate) fn clear_poison(&self) {}
+    }
+
+    pub(crate) struct Condvar(parking_lot::Condvar);
+
+    impl Condvar {
+        pub(crate) const fn new() -> Self {
+            Condvar(parking_lot::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(
+            &self,
+            mut guard: MutexGuard<'a, T>,
+        ) -> LockResult<MutexGuard<'a, T>> {
+            #[cfg(not(feature = "fair"))]
+            self.0.wait(&mut guard);
+            #[cfg(feature = "fair")]
+            self.0.wait(guard.inner());
+            Ok(guard)
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod spin_mutex {
+    use super::no_std::{LockResult, TryLockError, TryLockResult};
+
+    pub(crate) use spin::MutexGuard;
+
+    pub(crate) struct Mutex<T>(spin::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(v: T) -> Self {
+            Mutex(spin::Mutex::new(v))
+        }
+
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            Ok(self.0.lock())
+        }
+
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock().ok_or(TryLockError::WouldBlock)
+        }
+
+        pub(crate) fn get_mut(&mut self) -> LockResult<&mut T> {
+            Ok(self.0.get_mut())
+        }
+
+        pub(crate) fn into_inner(self) -> LockResult<T> {
+            Ok(self.0.into_inner())
+        }
+
+        pub(crate) fn is_poisoned(&self) -> bool {
+            false
+        }
+
+        pub(crate) fn clear_poison(&self) {}
+    }
+}
+
+/// Stand-ins for the parts of `std::sync` used by this
+/// crate. Spin locks are never poisoned, so the poisoned
+/// cases are never constructed.
+#[cfg(not(feature = "std"))]
+#[allow(dead_code)]
+mod no_std {
+    pub(crate) struct PoisonError<G>(G);
+
+    impl<G> PoisonError<G> {
+        pub(crate) fn into_inner(self) -> G {
+            self.0
+        }
+    }
+
+    impl<G> core::fmt::Debug for PoisonError<G> {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.write_str("PoisonError { .. }")
+        }
+    }
+
+    pub(crate) enum TryLockError<G> {
+        Poisoned(PoisonError<G>),
+        WouldBlock,
+    }
+
+    pub(crate) type LockResult<G> = Result<G, PoisonError<G>>;
+    pub(crate) type TryLockResult<G> = Result<G, TryLockError<G>>;
+
+    pub(crate) struct OnceCell<T>(spin::Once<T>);
+
+    impl<T> OnceCell<T> {
+        pub(crate) const fn new() -> Self {
+            OnceCell(spin::Once::new())
+        }
+
+        pub(crate) fn get(&self) -> Option<&T> {
+            self.0.get()
+        }
+
+        pub(crate) fn get_mut(&mut self) -> Option<&mut T> {
+            self.0.get_mut()
+        }
+
+        pub(crate) fn into_inner(self) -> Option<T> {
+            self.0.try_into_inner()
+        }
+
+        pub(crate) fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+            self.0.call_once(f)
+        }
+    }
+}
+
+/// Record of which thread, if any, holds a global's lock,
+/// used to catch a thread fetching a global it already
+/// holds, which would otherwise deadlock. Threads are only
+/// identifiable with `std`, so without it this does nothing.
+pub(crate) struct Holder {
+    #[cfg(feature = "std")]
+    thread: HolderCell,
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+type HolderCell = core::sync::atomic::AtomicUsize;
+
+/// Under `--cfg loom`, the holder atomic is modelled as a
+/// value under a `loom` mutex, made on first use since `loom`
+/// types cannot be built in a `const`. A `loom` atomic made
+/// on first use would not be ordered after its creation, and
+/// `loom` would reject every access from another thread.
+#[cfg(all(feature = "std", loom))]
+struct HolderCell(std::sync::OnceLock<loom::sync::Mutex<usize>>);
+
+#[cfg(all(feature = "std", loom))]
+impl HolderCell {
+    const fn new(_: usize) -> Self {
+        HolderCell(std::sync::OnceLock::new())
+    }
+
+    fn get(&self) -> loom::sync::MutexGuard<'_, usize> {
+        self.0
+            .get_or_init(|| loom::sync::Mutex::new(0))
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn load(&self, _: core::sync::atomic::Ordering) -> usize {
+        *self.get()
+    }
+
+    fn store(&self, v: usize, _: core::sync::atomic::Ordering) {
+        *self.get() = v;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Holder {
+    pub(crate) const fn new() -> Self {
+        Holder {
+            thread: HolderCell::new(0),
+        }
+    }
+
+    /// A small nonzero id for the current thread, or 0 if the
+    /// thread is being torn down.
+    fn current_thread() -> usize {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT: AtomicUsize = AtomicUsize::new(1);
+        #[cfg(not(loom))]
+        std::thread_local! {
+            static ID: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+        }
+        // `loom` runs its threads on one real thread.
+        #[cfg(loom)]
+        loom::thread_local! {
+            static ID: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+        }
+        ID.try_with(|id| *id).unwrap_or(0)
+    }
+
+    /// Report whether the current thread holds the lock. Only
+    /// the current thread ever stores its own id, so relaxed
+    /// ordering suffices.
+    pub(crate) fn is_current(&self) -> bool {
+        let me = Self::current_thread();
+        me != 0 && self.thread.load(core::sync::atomic::Ordering::Relaxed) == me
+    }
+
+    pub(crate) fn set(&self) {
+        let me = Self::current_thread();
+        self.thread.store(me, core::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.thread.store(0, core::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Holder {
+    pub(crate) const fn new() -> Self {
+        Holder {}
+    }
+
+    pub(crate) fn is_current(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn set(&self) {}
+
+    pub(crate) fn clear(&self) {}
+}
+
+impl Holder {
+    /// Panic if the current thread holds the lock.
+    #[track_caller]
+    pub(crate) fn check(&self) {
+        if self.is_current() {
+            panic!("re-entrant fetch on already-locked global");
+        }
+    }
+
+    /// Record that the current thread holds the lock until the
+    /// result is dropped, even by unwinding. For code that runs
+    /// under the lock without a guard, such as an initializer.
+    pub(crate) fn hold(&self) -> Held<'_> {
+        self.set();
+        Held(self)
+    }
+}
+
+/// Clears the holder when dropped, produced by
+/// `Holder::hold()`.
+pub(crate) struct Held<'a>(&'a Holder);
+
+impl Drop for Held<'_> {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
@@ -0,0 +1,57 @@
+//! Lock contention statistics, kept with the `metrics`
+//! feature.
+
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Lock contention statistics for a `Global`, produced by
+/// `Global::stats()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GlobalStats {
+    /// Number of times the global's lock has been acquired.
+    pub acquisitions: u64,
+    /// Total time spent waiting to acquire the lock, including
+    /// waits that timed out or found the lock held.
+    pub total_wait: Duration,
+    /// Longest single wait to acquire the lock, whether or
+    /// not it was acquired.
+    pub max_wait: Duration,
+}
+
+/// Running statistics stored alongside a global's lock. Wait
+/// times are kept in nanoseconds.
+pub(crate) struct Metrics {
+    acquisitions: AtomicU64,
+    total_wait: AtomicU64,
+    max_wait: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) const fn new() -> Self {
+        Metrics {
+            acquisitions: AtomicU64::new(0),
+            total_wait: AtomicU64::new(0),
+            max_wait: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a wait of `wait` for the lock, which was
+    /// `acquired` or given up on.
+    pub(crate) fn record(&self, wait: Duration, acquired: bool) {
+        let nanos = u64::try_from(wait.as_nanos()).unwrap_or(u64::MAX);
+        if acquired {
+            self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.total_wait.fetch_add(nanos, Ordering::Relaxed);
+        self.max_wait.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> GlobalStats {
+        GlobalStats {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.total_wait.load(Ordering::Relaxed)),
+            max_wait: Duration::from_nanos(self.max_wait.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Lock-order validation, with the `lock_order` feature in
+//! debug builds. Each thread keeps a list of the globals it
+//! holds, and every time a global is locked while others are
+//! held, the order is recorded. Blocking on a global that has
+//! previously been locked, directly or indirectly, before one
+//! currently held could deadlock, and panics. Trying to lock
+//! a global without blocking cannot deadlock, so it is never
+//! checked, but its order is recorded if it succeeds.
+//!
+//! Globals are told apart by address, and the recorded order
+//! is never forgotten. This suits `static` globals, which
+//! never move or go away. A `Global` that is not a `static`
+//! may be dropped or moved and another put at its old
+//! address, which then inherits its order and may panic
+//! falsely.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+/// Pairs `(a, b)` of global ids such that `b` has been locked
+/// while `a` was held.
+static ORDER: Mutex<BTreeSet<(usize, usize)>> = Mutex::new(BTreeSet::new());
+
+std::thread_local! {
+    /// The ids and names of the globals held by this thread,
+    /// in the order they were locked.
+    static HELD: RefCell<Vec<(usize, Option<&'static str>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Whether `to` has been locked after `from`, perhaps by way
+/// of other globals.
+fn reaches(order: &BTreeSet<(usize, usize)>, from: usize, to: usize) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![from];
+    while let Some(a) = pending.pop() {
+        if a == to {
+            return true;
+        }
+        if seen.insert(a) {
+            pending.extend(order.range((a, 0)..=(a, usize::MAX)).map(|&(_, b)| b));
+        }
+    }
+    false
+}
+
+/// Check that the global `id` may be locked by this thread
+/// given the globals it holds, and record the order.
+///
+/// # Panics
+///
+/// Will panic if `id` has been locked before a global this
+/// thread holds.
+#[track_caller]
+pub(crate) fn check(id: usize, name: Option<&'static str>) {
+    let violation = HELD.with(|held| {
+        let held = held.borrow();
+        if held.is_empty() {
+            return None;
+        }
+        let mut order = ORDER.lock().unwrap_or_else(PoisonError::into_inner);
+        for &(h, h_name) in held.iter().filter(|&&(h, _)| h != id) {
+            if reaches(&order, id, h) {
+                return Some(h_name);
+            }
+            order.insert((h, id));
+        }
+        None
+    });
+    if let Some(h_name) = violation {
+        panic!(
+            "lock order violation: global `{}` locked while holding `{}`, \
+             which has been locked while holding it",
+            name.unwrap_or("<unnamed>"),
+            h_name.unwrap_or("<unnamed>"),
+        );
+    }
+}
+
+/// Record the order of the global `id`, just locked without
+/// blocking by this thread, after the globals it holds.
+pub(crate) fn record(id: usize) {
+    HELD.with(|held| {
+        let held = held.borrow();
+        if held.is_empty() {
+            return;
+        }
+        let mut order = ORDER.lock().unwrap_or_else(PoisonError::into_inner);
+        for &(h, _) in held.iter().filter(|&&(h, _)| h != id) {
+            order.insert((h, id));
+        }
+    });
+}
+
+/// Record that this thread now holds the global `id`.
+pub(crate) fn acquired(id: usize, name: Option<&'static str>) {
+    HELD.with(|held| held.borrow_mut().push((id, name)));
+}
+
+/// Record that this thread no longer holds the global `id`.
+pub(crate) fn released(id: usize) {
+    // The thread-local may already be gone if a guard is
+    // dropped during thread teardown.
+    let _ = HELD.try_with(|held| {
+        let mut held = held.borrow_mut();
+        if let Some(i) = held.iter().rposition(|&(h, _)| h == id) {
+            held.remove(i);
+        }
+    });
+}
@@ -0,0 +1,192 @@
+//! Registry of the `Global`s declared with `global_vars!`,
+//! kept with the `testing` feature so that tests can list and
+//! reset every global at once.
+
+use core::fmt;
+
+use crate::sync::Global;
+
+/// The parts of the `Global` API that do not depend on the
+/// type of its value.
+#[doc(hidden)]
+pub trait AnyGlobal: Sync {
+    fn name(&self) -> Option<&'static str>;
+    fn is_initialized(&self) -> bool;
+    fn reset(&self);
+}
+
+impl<T: Send + 'static> AnyGlobal for Global<T> {
+    fn name(&self) -> Option<&'static str> {
+        Global::name(self)
+    }
+
+    fn is_initialized(&self) -> bool {
+        Global::is_initialized(self)
+    }
+
+    fn reset(&self) {
+        Global::reset(self);
+    }
+}
+
+/// Registry entry for a declared global. Only for use by
+/// `global_vars!`.
+#[doc(hidden)]
+pub struct Registered {
+    pub global: &'static dyn AnyGlobal,
+    /// The global as `Debug`, if its value is.
+    pub debug: fn() -> Option<&'static (dyn fmt::Debug + Sync)>,
+}
+
+/// Wrapper used by `global_vars!` to find out whether a
+/// global's value is `Debug`. Calling `debug()` on a
+/// `&&DebugProbe` picks the `ViaDebug` method when it
+/// applies and falls back to `ViaNoDebug` otherwise.
+#[doc(hidden)]
+pub struct DebugProbe<T: 'static>(pub &'static Global<T>);
+
+#[doc(hidden)]
+pub trait ViaDebug {
+    fn debug(&self) -> Option<&'static (dyn fmt::Debug + Sync)>;
+}
+
+impl<T: fmt::Debug + Send> ViaDebug for &DebugProbe<T> {
+    fn debug(&self) -> Option<&'static (dyn fmt::Debug + Sync)> {
+        Some(self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait ViaNoDebug {
+    fn debug(&self) -> Option<&'static (dyn fmt::Debug + Sync)> {
+        None
+    }
+}
+
+impl<T> ViaNoDebug for DebugProbe<T> {}
+
+inventory::collect!(Registered);
+
+/// Reset every `Global` declared with `global_vars!`, as by
+/// `Global::reset()`. Intended for teardown between tests
+/// that share globals; only available with the `testing`
+/// feature. See `reset_all!()` for the macro normally used
+/// here.
+///
+/// # Panics
+///
+/// Will panic if the current thread holds any of the globals.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: usize;
+///     Y: Vec<usize> = vec![1];
+/// }
+///
+/// init!(X = 1);
+/// Y.fetch().push(2);
+/// reset_all();
+/// assert!(!X.is_initialized());
+/// assert_eq!(*Y.fetch(), [1]);
+/// ```
+pub fn reset_all() {
+    for r in inventory::iter::<Registered> {
+        r.global.reset();
+    }
+}
+
+/// Description of a registered global, produced by
+/// `registered()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalInfo {
+    /// The declared name of the global.
+    pub name: &'static str,
+    /// Whether the global was initialized when it was
+    /// listed.
+    pub initialized: bool,
+}
+
+/// The number of `Global`s declared with `global_vars!`. Only
+/// available with the `testing` feature.
+pub fn count_registered() -> usize {
+    inventory::iter::<Registered>.into_iter().count()
+}
+
+/// Describe each `Global` declared with `global_vars!`, in no
+/// particular order. The globals' locks are not waited for:
+/// see `Global::is_initialized()`. Only available with the
+/// `testing` feature.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     READY: bool;
+///     PENDING: bool;
+/// }
+///
+/// init!(READY = true);
+/// assert_eq!(count_registered(), 2);
+/// let mut globals: Vec<_> = registered().collect();
+/// globals.sort_by_key(|g| g.name);
+/// assert_eq!(globals[0].name, "PENDING");
+/// assert!(!globals[0].initialized);
+/// assert_eq!(globals[1].name, "READY");
+/// assert!(globals[1].initialized);
+/// ```
+pub fn registered() -> impl Iterator<Item = GlobalInfo> {
+    inventory::iter::<Registered>
+        .into_iter()
+        .map(|r| GlobalInfo {
+            name: r.global.name().unwrap_or("<unnamed>"),
+            initialized: r.global.is_initialized(),
+        })
+}
+
+/// Install a panic hook that, after running the previous hook,
+/// lists every `Global` declared with `global_vars!` on
+/// standard error, with its value if the value is `Debug`.
+/// A global that is locked, for example by the panicking
+/// thread, is shown as `<locked>` rather than waited for.
+/// Only available with the `testing` and `std` features.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use ugly_global::*;
+/// struct Handle;
+/// global_vars! {
+///     REQUESTS: usize;
+///     QUEUE: Vec<usize>;
+///     HANDLE: Handle;
+/// }
+///
+/// install_crash_dumper();
+/// init!(REQUESTS = 3, QUEUE = vec![1, 2], HANDLE = Handle);
+/// // Reports `Global { name: "REQUESTS", value: 3 }`,
+/// // `Global { name: "QUEUE", value: <locked> }` and
+/// // `Global { name: "HANDLE", value: <not Debug> }`.
+/// let _queue = QUEUE.fetch();
+/// panic!("out of cheese");
+/// ```
+#[cfg(feature = "std")]
+pub fn install_crash_dumper() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        eprintln!("globals at panic:");
+        for r in inventory::iter::<Registered> {
+            match (r.debug)() {
+                Some(g) => eprintln!("    {:?}", g),
+                None => eprintln!(
+                    "    Global {{ name: {:?}, value: <not Debug> }}",
+                    r.global.name().unwrap_or("<unnamed>"),
+                ),
+            }
+        }
+    }));
+}
@@ -0,0 +1,213 @@
+use std::sync::{
+    PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError, TryLockResult,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::lock::OnceCell;
+
+/// Global type backed by a reader-writer lock. Any number of
+/// threads may read the global at once, but writes are
+/// exclusive.
+pub struct RwGlobal<T>(OnceCell<RwLock<T>>);
+
+impl<T: 'static> RwGlobal<T> {
+    /// Get a new, uninitialized reader-writer global.
+    pub const fn new() -> Self {
+        RwGlobal(OnceCell::new())
+    }
+
+    /// Report whether the global has been initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.0.get().is_some()
+    }
+
+    /// Acquire shared read access to a global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the underlying lock gets poisoned (should
+    /// not happen).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     rwsync X: Vec<usize>;
+    /// }
+    ///
+    /// X.init(vec![1, 2]);
+    /// let a = X.read();
+    /// let b = X.read();
+    /// assert_eq!(a.len() + b.len(), 4);
+    /// ```
+    #[track_caller]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0
+            .get()
+            .expect("global uninitialized")
+            .read()
+            .expect("global lock poisoned")
+    }
+
+    /// Acquire exclusive write access to a global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the underlying lock gets poisoned (should
+    /// not happen).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     rwsync X: Vec<usize>;
+    /// }
+    ///
+    /// X.init(Vec::new());
+    /// X.write().push(1);
+    /// assert_eq!(*X.read(), [1]);
+    /// ```
+    #[track_caller]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.0
+            .get()
+            .expect("global uninitialized")
+            .write()
+            .expect("global lock poisoned")
+    }
+
+    /// Acquire shared read access to a global, giving up if
+    /// it cannot be acquired within `dur`, for example because
+    /// a writer holds the lock.
+    ///
+    /// Returns `None` on timeout.
+    ///
+    /// # Panics
+    ///
+    /// See `read()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     rwsync X: usize;
+    /// }
+    ///
+    /// X.init(0);
+    /// let wait = Duration::from_millis(10);
+    /// assert!(X.try_read_for(wait).is_some());
+    /// let w = X.write();
+    /// let t = std::thread::spawn(move || X.try_read_for(wait).is_none());
+    /// assert!(t.join().unwrap());
+    /// drop(w);
+    /// ```
+    #[track_caller]
+    pub fn try_read_for(&self, dur: Duration) -> Option<RwLockReadGuard<'_, T>> {
+        let lock = self.0.get().expect("global uninitialized");
+        poll_for(dur, || lock.try_read())
+    }
+
+    /// Acquire exclusive write access to a global, giving up
+    /// if it cannot be acquired within `dur`.
+    ///
+    /// Returns `None` on timeout.
+    ///
+    /// # Panics
+    ///
+    /// See `write()`.
+    #[track_caller]
+    pub fn try_write_for(&self, dur: Duration) -> Option<RwLockWriteGuard<'_, T>> {
+        let lock = self.0.get().expect("global uninitialized");
+        poll_for(dur, || lock.try_write())
+    }
+
+    /// Acquire write access to a global, run `f` on its value,
+    /// and release the lock before returning the result of
+    /// `f`.
+    ///
+    /// # Panics
+    ///
+    /// See `write()`.
+    #[track_caller]
+    pub fn with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        f(&mut self.write())
+    }
+
+    /// Acquire read access to a global, run `f` on its value,
+    /// and release the lock before returning the result of
+    /// `f`. Other readers are not blocked meanwhile.
+    ///
+    /// # Panics
+    ///
+    /// See `read()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     rwsync X: Vec<usize>;
+    /// }
+    ///
+    /// X.init(vec![1, 2]);
+    /// X.with(|x| x.push(3));
+    /// assert_eq!(X.with_ref(|x| x.len()), 3);
+    /// ```
+    #[track_caller]
+    pub fn with_ref<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        f(&self.read())
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value.  See `init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on initialization failure; for example on an attempt
+    /// to reinitialize a variable.
+    #[track_caller]
+    pub fn init(&self, v: T) {
+        if self.try_init(v).is_err() {
+            panic!("initialization failed");
+        }
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value, without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the global
+    /// has already been initialized.
+    pub fn try_init(&self, v: T) -> Result<(), T> {
+        self.0
+            .set(RwLock::new(v))
+            .map_err(|l| l.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl<T: 'static> Default for RwGlobal<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The standard reader-writer lock has no timed lock, so poll
+/// with `try_lock` until the deadline, yielding between
+/// attempts.
+fn poll_for<G, F: FnMut() -> TryLockResult<G>>(dur: Duration, mut try_lock: F) -> Option<G> {
+    let deadline = Instant::now() + dur;
+    loop {
+        match try_lock() {
+            Ok(guard) => return Some(guard),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => thread::yield_now(),
+            Err(TryLockError::WouldBlock) => return None,
+            Err(TryLockError::Poisoned(_)) => panic!("global lock poisoned"),
+        }
+    }
+}
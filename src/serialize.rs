@@ -0,0 +1,86 @@
+//! `serde` support for `Global`, with the `serde` feature.
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Error, Serialize, Serializer};
+
+use crate::sync::Global;
+
+/// Serializes the value of the global as `Some(value)`, or as
+/// `None` if it is uninitialized. The global is locked while
+/// it is serialized. Serializing has no side effects: a lazy
+/// initializer is not run, so a global with an initializer
+/// that has not yet been accessed serializes as `None`.
+///
+/// # Panics
+///
+/// Will panic if the current thread already holds the
+/// global, rather than deadlocking.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: Vec<usize>;
+///     Y: Vec<usize> = vec![3];
+/// }
+///
+/// assert_eq!(serde_json::to_string(&X).unwrap(), "null");
+/// init!(X = vec![1, 2]);
+/// assert_eq!(serde_json::to_string(&X).unwrap(), "[1,2]");
+///
+/// assert_eq!(serde_json::to_string(&Y).unwrap(), "null");
+/// assert!(!Y.is_initialized());
+/// Y.fetch().push(4);
+/// assert_eq!(serde_json::to_string(&Y).unwrap(), "[3,4]");
+/// ```
+impl<T: Serialize + 'static> Serialize for Global<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.with_current(|v| match v {
+            Some(v) => serializer.serialize_some(v),
+            None => serializer.serialize_none(),
+        })
+        .unwrap_or_else(|e| Err(S::Error::custom(e)))
+    }
+}
+
+impl<T: 'static> Global<T> {
+    /// Set the value of a global from serialized data, in the
+    /// form produced by serializing a `Global`. A `None`
+    /// value leaves the global uninitialized, as by
+    /// `deinit()`. Only available with the `serde` feature.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize>;
+    /// }
+    ///
+    /// let mut de = serde_json::Deserializer::from_str("[1, 2]");
+    /// X.deserialize_into(&mut de).unwrap();
+    /// assert_eq!(*X.fetch(), [1, 2]);
+    /// let mut de = serde_json::Deserializer::from_str("null");
+    /// X.deserialize_into(&mut de).unwrap();
+    /// assert!(!X.is_initialized());
+    /// ```
+    pub fn deserialize_into<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        T: Deserialize<'de>,
+    {
+        match Option::<T>::deserialize(deserializer)? {
+            Some(v) => self.set(v),
+            None => drop(self.deinit()),
+        }
+        Ok(())
+    }
+}
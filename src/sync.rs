@@ -0,0 +1,2573 @@
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(debug_assertions)]
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use core::task::{Context, Poll};
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+use crate::error::GlobalError;
+#[cfg(feature = "std")]
+use crate::error::{EnvError, TimeoutError};
+#[cfg(feature = "std")]
+use crate::lock::Condvar;
+use crate::lock::{Holder, Mutex, MutexGuard, OnceCell, PoisonError, TryLockError};
+#[cfg(feature = "metrics")]
+use crate::metrics::{GlobalStats, Metrics};
+use crate::wakers::{WakeOnDrop, Wakers};
+
+/// Result of locking a global's mutex.
+type LockResult<'a, T> = Result<MutexGuard<'a, Option<T>>, PoisonError<MutexGuard<'a, Option<T>>>>;
+
+/// Result of trying to lock a global's mutex.
+type TryLockResult<'a, T> =
+    Result<MutexGuard<'a, Option<T>>, TryLockError<MutexGuard<'a, Option<T>>>>;
+
+/// Global type.
+///
+/// The value is kept as an `Option` inside the mutex so that a
+/// global can be returned to the uninitialized state.
+///
+/// # Thread safety
+///
+/// A `static` must be `Sync`, and `Global<T>` is `Sync` only
+/// when `T` is `Send`, since the value is handed from thread
+/// to thread. A value that cannot be sent between threads,
+/// such as an `Rc`, can be kept in a `LocalGlobal` instead.
+/// The compiler reports the missing `Send` at the declaration
+/// of the global:
+///
+/// ```compile_fail,E0277
+/// use std::rc::Rc;
+/// use ugly_global::*;
+/// global_vars! {
+///     // `Rc<usize>` cannot be sent between threads safely
+///     SHARED: Rc<usize>;
+/// }
+/// ```
+///
+/// The methods themselves do not require `T: Send`, so that a
+/// `Global` that is not a `static` can hold any value.
+pub struct Global<T> {
+    cell: OnceCell<Mutex<Option<T>>>,
+    holder: Holder,
+    /// Signalled when the global is given a value, for
+    /// `wait_until_initialized()`.
+    #[cfg(feature = "std")]
+    ready: Condvar,
+    lazy: Option<fn() -> T>,
+    name: Option<&'static str>,
+    /// Set when a fetch panicked for want of a value, so that
+    /// a late `init()` can say so. Debug builds only.
+    #[cfg(debug_assertions)]
+    fetched_early: AtomicBool,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    /// Tasks waiting in `poll_fetch()` for the global to be
+    /// unlocked.
+    wakers: Wakers,
+    /// Callbacks registered with `on_change()`.
+    #[cfg(feature = "std")]
+    observers: OnceCell<std::sync::Mutex<Vec<Observer<T>>>>,
+}
+
+/// Callback run on each change to a global's value.
+#[cfg(feature = "std")]
+type Observer<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+/// Global type for trait objects and other unsized values,
+/// which are kept boxed. A guard on a `BoxGlobal<dyn Trait>`
+/// derefs to the box, through which the trait's methods can
+/// be called directly; `GlobalGuard::map()` gives a guard
+/// that derefs to the trait object itself. `global_vars!`
+/// declares one for an entry whose type starts with `dyn`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// trait Backend: Send {
+///     fn name(&self) -> String;
+///     fn rename(&mut self, name: &str);
+/// }
+/// struct Memory(String);
+/// impl Backend for Memory {
+///     fn name(&self) -> String {
+///         self.0.clone()
+///     }
+///     fn rename(&mut self, name: &str) {
+///         self.0 = name.to_string();
+///     }
+/// }
+///
+/// static BACKEND: BoxGlobal<dyn Backend> = Global::new();
+///
+/// BACKEND.init(Box::new(Memory("memory".to_string())));
+/// BACKEND.fetch().rename("scratch");
+/// let backend = BACKEND.fetch().map(|b| &**b, |b| &mut **b);
+/// let backend: &dyn Backend = &*backend;
+/// assert_eq!(backend.name(), "scratch");
+/// ```
+#[cfg(feature = "std")]
+pub type BoxGlobal<T> = Global<Box<T>>;
+
+/// Guard type giving access to the value of a locked
+/// `Global`. The global is unlocked when the guard is dropped.
+pub struct GlobalGuard<'a, T> {
+    guard: MutexGuard<'a, Option<T>>,
+    holder: &'a Holder,
+    /// The `id()` of the global, for lock-order validation.
+    #[cfg(all(feature = "lock_order", debug_assertions))]
+    id: usize,
+    // Declared after `guard` so that waiting tasks are woken
+    // once the mutex is unlocked.
+    _wake: WakeOnDrop<'a>,
+}
+
+impl<'a, T> GlobalGuard<'a, T> {
+    fn new(guard: MutexGuard<'a, Option<T>>, global: &'a Global<T>) -> Option<Self> {
+        if guard.is_some() {
+            global.holder.set();
+            #[cfg(all(feature = "lock_order", debug_assertions))]
+            crate::order::acquired(global as *const Global<T> as usize, global.name);
+            Some(GlobalGuard {
+                guard,
+                holder: &global.holder,
+                #[cfg(all(feature = "lock_order", debug_assertions))]
+                id: global as *const Global<T> as usize,
+                _wake: WakeOnDrop::new(&global.wakers),
+            })
+        } else {
+            // Waiting tasks must still hear of the unlock.
+            drop(guard);
+            drop(WakeOnDrop::new(&global.wakers));
+            None
+        }
+    }
+
+    /// Release the lock on the global. This is the same as
+    /// dropping the guard, but makes the end of the critical
+    /// section explicit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize> = vec![1, 2, 3];
+    /// }
+    ///
+    /// let x = X.fetch();
+    /// let total: usize = x.iter().sum();
+    /// x.unlock();
+    /// // Slow work with `total` need not hold the lock.
+    /// assert_eq!(total, 6);
+    /// assert!(X.try_fetch_now().is_some());
+    /// ```
+    pub fn unlock(self) {}
+
+    /// Run `f` on the value of the global, then release the
+    /// lock and return the result of `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize = 1;
+    /// }
+    ///
+    /// let x = X.fetch().into_inner_with(|x| {
+    ///     *x += 1;
+    ///     *x
+    /// });
+    /// assert_eq!(x, 2);
+    /// ```
+    pub fn into_inner_with<R, F: FnOnce(&mut T) -> R>(mut self, f: F) -> R {
+        f(&mut self)
+    }
+
+    /// Run `f` on the value of the global and return its
+    /// result, keeping the lock. This is useful for getting an
+    /// intermediate result partway through a series of
+    /// changes; see `into_inner_with()` to release the lock
+    /// afterward instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// struct Counter { hits: usize, log: Vec<String> };
+    /// global_vars! {
+    ///     COUNTER: Counter = Counter { hits: 0, log: Vec::new() };
+    /// }
+    ///
+    /// let mut counter = COUNTER.fetch();
+    /// let hits = counter.bump(|c| {
+    ///     c.hits += 1;
+    ///     c.hits
+    /// });
+    /// counter.log.push(format!("hit {}", hits));
+    /// assert_eq!(counter.log, ["hit 1"]);
+    /// ```
+    pub fn bump<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> R {
+        f(self)
+    }
+
+    /// Narrow a guard to a part of the global's value, such
+    /// as a field, keeping the global locked.
+    ///
+    /// Since this crate has no unsafe code, the narrowed
+    /// reference cannot be stored alongside the guard it
+    /// borrows from. Instead the projection is re-applied on
+    /// each access, which requires both a shared and a mutable
+    /// version of it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// struct S { x: usize, y: usize };
+    /// global_vars! {
+    ///     X: S;
+    /// }
+    ///
+    /// init!(X = S { x: 0, y: 0 });
+    /// let mut x = X.fetch().map(|s| &s.x, |s| &mut s.x);
+    /// *x += 1;
+    /// drop(x);
+    /// assert_eq!(X.fetch().x, 1);
+    /// ```
+    pub fn map<U: ?Sized>(
+        self,
+        get: fn(&T) -> &U,
+        get_mut: fn(&mut T) -> &mut U,
+    ) -> MappedGuard<'a, T, U> {
+        MappedGuard {
+            guard: self,
+            get,
+            get_mut,
+        }
+    }
+}
+
+impl<T> Deref for GlobalGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.guard.as_ref().expect("global uninitialized")
+    }
+}
+
+impl<T> DerefMut for GlobalGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_mut().expect("global uninitialized")
+    }
+}
+
+impl<T> Drop for GlobalGuard<'_, T> {
+    fn drop(&mut self) {
+        self.holder.clear();
+        #[cfg(all(feature = "lock_order", debug_assertions))]
+        crate::order::released(self.id);
+    }
+}
+
+/// Formats the value of the global.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     NAME: String = "x".to_string();
+/// }
+///
+/// let name = NAME.fetch();
+/// assert_eq!(format!("{} {:?}", name, name), r#"x "x""#);
+/// ```
+impl<T: fmt::Display> fmt::Display for GlobalGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// Formats the value of the global.
+impl<T: fmt::Debug> fmt::Debug for GlobalGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Guard type giving access to part of the value of a locked
+/// `Global`, produced by `GlobalGuard::map()`. The global is
+/// unlocked when the guard is dropped.
+pub struct MappedGuard<'a, T, U: ?Sized> {
+    guard: GlobalGuard<'a, T>,
+    get: fn(&T) -> &U,
+    get_mut: fn(&mut T) -> &mut U,
+}
+
+impl<T, U: ?Sized> Deref for MappedGuard<'_, T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        (self.get)(&self.guard)
+    }
+}
+
+impl<T, U: ?Sized> DerefMut for MappedGuard<'_, T, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        (self.get_mut)(&mut self.guard)
+    }
+}
+
+/// Guard type that restores the previous value of a `Global`
+/// when dropped, produced by `Global::scoped_override()`.
+pub struct OverrideGuard<'a, T: 'static> {
+    global: &'a Global<T>,
+    old: Option<T>,
+}
+
+impl<T: 'static> Drop for OverrideGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(old) = self.old.take() {
+            // Restoring must not panic, since this may run while
+            // unwinding from a failed test: poisoning is
+            // ignored, a `deinit()`ed global gets its value
+            // back, and a global this thread still holds is
+            // left alone.
+            let global = self.global;
+            if global.holder.is_current() {
+                return;
+            }
+            let m = global.cell.get_or_init(|| Mutex::new(None));
+            let mut guard = global
+                .lock_unchecked(m)
+                .unwrap_or_else(PoisonError::into_inner);
+            *guard = Some(old);
+            if let Some(guard) = GlobalGuard::new(guard, global) {
+                global.changed(&guard);
+            }
+            global.notify_initialized();
+        }
+    }
+}
+
+/// Future that resolves to a guard on a `Global` once its lock
+/// is free, produced by `Global::fetch_async()`.
+#[cfg(feature = "async")]
+#[must_use = "futures do nothing unless awaited or polled"]
+pub struct FetchFuture<'a, T: 'static> {
+    global: &'a Global<T>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, T: 'static> Future for FetchFuture<'a, T> {
+    type Output = GlobalGuard<'a, T>;
+
+    #[track_caller]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<GlobalGuard<'a, T>> {
+        self.global.poll_fetch(cx)
+    }
+}
+
+impl<T: 'static> Global<T> {
+    /// Global `OnceCell` function --- used to get a new
+    /// `OnceCell` with `once_cell` in scope.
+    pub const fn new() -> Self {
+        Self::build(None, None)
+    }
+
+    /// Get a new global with the given name, which will be
+    /// used in panic messages and in `Debug` output.
+    /// `global_vars!` uses this to name each global after its
+    /// declared identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// static X: Global<usize> = Global::new_named("X");
+    ///
+    /// // Panics with "global `X` fetched before init!".
+    /// X.fetch();
+    /// ```
+    pub const fn new_named(name: &'static str) -> Self {
+        Self::build(None, Some(name))
+    }
+
+    /// Get a new global that will be initialized with the
+    /// result of `f` on first access, if it has not been
+    /// initialized explicitly by then. `global_vars!` uses
+    /// this for declarations with an initializer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// static X: Global<Vec<usize>> = Global::new_lazy(|| vec![1]);
+    ///
+    /// X.fetch().push(2);
+    /// assert_eq!(*X.fetch(), [1, 2]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// The initializer runs with the global locked, so one that
+    /// fetches the global itself panics, rather than
+    /// deadlocking:
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     S: usize = S.get_copy() + 1;
+    /// }
+    ///
+    /// S.get_copy();
+    /// ```
+    pub const fn new_lazy(f: fn() -> T) -> Self {
+        Self::build(Some(f), None)
+    }
+
+    /// Get a new global already holding `v`, so that it can be
+    /// fetched without an `init()`. This suits a `Global` made
+    /// at runtime, such as a struct field; a `static` needs one
+    /// of the `const` constructors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// struct Server {
+    ///     hits: Global<usize>,
+    /// }
+    ///
+    /// let server = Server { hits: Global::from_value(0) };
+    /// *server.hits.fetch() += 1;
+    /// assert_eq!(*server.hits.fetch(), 1);
+    /// ```
+    pub fn from_value(v: T) -> Self {
+        let global = Self::new();
+        global.cell.get_or_init(|| Mutex::new(Some(v)));
+        global
+    }
+
+    /// Both `new_lazy()` and `new_named()`. Only for use by
+    /// `global_vars!`.
+    #[doc(hidden)]
+    pub const fn new_lazy_named(f: fn() -> T, name: &'static str) -> Self {
+        Self::build(Some(f), Some(name))
+    }
+
+    const fn build(lazy: Option<fn() -> T>, name: Option<&'static str>) -> Self {
+        Global {
+            cell: OnceCell::new(),
+            holder: Holder::new(),
+            #[cfg(feature = "std")]
+            ready: Condvar::new(),
+            lazy,
+            name,
+            #[cfg(debug_assertions)]
+            fetched_early: AtomicBool::new(false),
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
+            wakers: Wakers::new(),
+            #[cfg(feature = "std")]
+            observers: OnceCell::new(),
+        }
+    }
+
+    /// Panic on access to an uninitialized global.
+    #[cold]
+    #[track_caller]
+    fn uninitialized(&self) -> ! {
+        #[cfg(debug_assertions)]
+        self.fetched_early.store(true, Ordering::Relaxed);
+        match self.name {
+            Some(name) => panic!("global `{}` fetched before init!", name),
+            None => panic!("global uninitialized"),
+        }
+    }
+
+    /// Panic if an earlier fetch panicked for want of a value,
+    /// which usually means that the global is being
+    /// initialized too late. Debug builds only.
+    #[track_caller]
+    fn check_fetched_early(&self) {
+        #[cfg(debug_assertions)]
+        {
+            if self.fetched_early.load(Ordering::Relaxed) {
+                panic!("init() called after first fetch()");
+            }
+        }
+    }
+
+    /// Forget any earlier fetch of the uninitialized global,
+    /// once it has been returned to the uninitialized state.
+    fn clear_fetched_early(&self) {
+        #[cfg(debug_assertions)]
+        self.fetched_early.store(false, Ordering::Relaxed);
+    }
+
+    /// Unwrap `x`, panicking as on access to an uninitialized
+    /// global if it is `None`.
+    #[track_caller]
+    fn or_uninitialized<U>(&self, x: Option<U>) -> U {
+        match x {
+            Some(x) => x,
+            None => self.uninitialized(),
+        }
+    }
+
+    /// The underlying mutex, if it exists yet. It always
+    /// exists for a lazily-initialized global.
+    fn mutex(&self) -> Option<&Mutex<Option<T>>> {
+        match self.lazy {
+            Some(_) => Some(self.cell.get_or_init(|| Mutex::new(None))),
+            None => self.cell.get(),
+        }
+    }
+
+    /// Wrap a locked mutex in a guard, first running the lazy
+    /// initializer if there is one and the global has no
+    /// value.
+    #[track_caller]
+    fn guard<'a>(&'a self, mut guard: MutexGuard<'a, Option<T>>) -> Option<GlobalGuard<'a, T>> {
+        if guard.is_none() {
+            if let Some(f) = self.lazy {
+                let held = self.holder.hold();
+                *guard = Some(f());
+                drop(held);
+                self.trace_init();
+            }
+        }
+        GlobalGuard::new(guard, self)
+    }
+
+    /// Lock `m`, which is this global's mutex, by `how`: one of
+    /// the mutex's blocking, non-blocking or timed locking
+    /// methods. Every lock of the global's value goes through
+    /// here, which records the wait with the `metrics`
+    /// feature. A wait that ends without the lock counts
+    /// toward the wait times but not the acquisitions.
+    fn acquire<'a, F>(&self, m: &'a Mutex<Option<T>>, how: F) -> TryLockResult<'a, T>
+    where
+        F: FnOnce(&'a Mutex<Option<T>>) -> TryLockResult<'a, T>,
+    {
+        #[cfg(feature = "metrics")]
+        {
+            let start = std::time::Instant::now();
+            let result = how(m);
+            let acquired = !matches!(result, Err(TryLockError::WouldBlock));
+            self.metrics.record(start.elapsed(), acquired);
+            result
+        }
+        #[cfg(not(feature = "metrics"))]
+        how(m)
+    }
+
+    /// Lock `m`, which is this global's mutex, waiting as long
+    /// as it takes, and checking the lock order with the
+    /// `lock_order` feature.
+    #[track_caller]
+    fn lock<'a>(&self, m: &'a Mutex<Option<T>>) -> LockResult<'a, T> {
+        #[cfg(all(feature = "lock_order", debug_assertions))]
+        crate::order::check(self.id(), self.name);
+        self.lock_unchecked(m)
+    }
+
+    /// Lock `m`, which is this global's mutex, as by `lock()`
+    /// but without checking the lock order, for paths that
+    /// must never panic.
+    fn lock_unchecked<'a>(&self, m: &'a Mutex<Option<T>>) -> LockResult<'a, T> {
+        match self.acquire(m, |m| m.lock().map_err(TryLockError::Poisoned)) {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(e)) => Err(e),
+            Err(TryLockError::WouldBlock) => unreachable!("blocking lock would block"),
+        }
+    }
+
+    /// Lock `m`, which is this global's mutex, if it is free.
+    /// This cannot deadlock, so the lock order is not checked,
+    /// only recorded on success.
+    fn try_lock<'a>(&self, m: &'a Mutex<Option<T>>) -> TryLockResult<'a, T> {
+        self.try_lock_with(m, Mutex::try_lock)
+    }
+
+    /// Lock `m`, which is this global's mutex, without
+    /// blocking indefinitely, using `how`, and record the lock
+    /// order with the `lock_order` feature if that succeeds.
+    fn try_lock_with<'a, F>(&self, m: &'a Mutex<Option<T>>, how: F) -> TryLockResult<'a, T>
+    where
+        F: FnOnce(&'a Mutex<Option<T>>) -> TryLockResult<'a, T>,
+    {
+        let result = self.acquire(m, how);
+        #[cfg(all(feature = "lock_order", debug_assertions))]
+        if result.is_ok() {
+            crate::order::record(self.id());
+        }
+        result
+    }
+
+    /// Log that the global has just been given a value, with
+    /// the `trace_init` feature. Called from every path that
+    /// initializes a global.
+    #[track_caller]
+    fn trace_init(&self) {
+        #[cfg(feature = "trace_init")]
+        {
+            let thread = std::thread::current();
+            log::debug!(
+                "initialized global `{}` on thread {} ({:?}) at {}",
+                self.name.unwrap_or("<unnamed>"),
+                thread.name().unwrap_or("<unnamed>"),
+                thread.id(),
+                core::panic::Location::caller(),
+            );
+        }
+    }
+
+    /// Wake any threads in `wait_until_initialized()`. Called
+    /// whenever the global may have been given a value.
+    fn notify_initialized(&self) {
+        #[cfg(feature = "std")]
+        self.ready.notify_all();
+    }
+
+    /// Run the `on_change()` callbacks on the new value `v`
+    /// of the global, which is still locked.
+    fn changed(&self, v: &T) {
+        #[cfg(feature = "std")]
+        {
+            if let Some(observers) = self.observers.get() {
+                let observers = observers.lock().unwrap_or_else(PoisonError::into_inner);
+                for f in observers.iter() {
+                    f(v);
+                }
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = v;
+    }
+
+    /// Lock the underlying mutex, creating it if needed and
+    /// ignoring poisoning.
+    #[track_caller]
+    fn lock_cell(&self) -> MutexGuard<'_, Option<T>> {
+        self.holder.check();
+        self.lock(self.cell.get_or_init(|| Mutex::new(None)))
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The name of the global, if it has one. `global_vars!`
+    /// names each global after its declared identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert_eq!(X.name(), Some("X"));
+    /// ```
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// A stable identity for the global: its address. Globals
+    /// are `static`, so this never changes, and distinct
+    /// globals have distinct ids. `fetch_all!()` and `swap()`
+    /// lock globals in id order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    ///     Y: usize;
+    /// }
+    ///
+    /// assert_eq!(X.id(), X.id());
+    /// assert_ne!(X.id(), Y.id());
+    /// ```
+    pub fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Report whether the global has been initialized. This
+    /// never waits for the global's lock: a locked global is
+    /// reported as initialized, since a guard always holds a
+    /// value. A global that another thread is initializing or
+    /// deinitializing at that moment may be reported either
+    /// way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert!(!X.is_initialized());
+    /// init!(X = 0);
+    /// assert!(X.is_initialized());
+    /// let x = X.fetch();
+    /// let t = std::thread::spawn(|| X.is_initialized());
+    /// assert!(t.join().unwrap());
+    /// drop(x);
+    /// ```
+    pub fn is_initialized(&self) -> bool {
+        let m = match self.cell.get() {
+            Some(m) => m,
+            None => return false,
+        };
+        let _wake = WakeOnDrop::new(&self.wakers);
+        match m.try_lock() {
+            Ok(guard) => guard.is_some(),
+            Err(TryLockError::Poisoned(e)) => e.into_inner().is_some(),
+            Err(TryLockError::WouldBlock) => true,
+        }
+    }
+
+    /// Lock a global and acquire the object used to access it. See
+    /// `fetch!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    /// Will panic if the underlying mutex gets poisoned (should
+    /// not happen).
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 0);
+    /// let _x = X.fetch();
+    /// let _y = X.fetch();
+    /// ```
+    #[track_caller]
+    pub fn fetch(&self) -> GlobalGuard<'_, T> {
+        match self.checked_fetch() {
+            Ok(guard) => guard,
+            Err(GlobalError::Uninitialized) => self.uninitialized(),
+            Err(GlobalError::Poisoned) => panic!("global lock poisoned"),
+        }
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// reporting failure as a `GlobalError` rather than
+    /// panicking.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert_eq!(X.checked_fetch().err(), Some(GlobalError::Uninitialized));
+    /// init!(X = 1);
+    /// assert_eq!(*X.checked_fetch().unwrap(), 1);
+    /// ```
+    #[track_caller]
+    pub fn checked_fetch(&self) -> Result<GlobalGuard<'_, T>, GlobalError> {
+        self.holder.check();
+        let guard = self
+            .lock(self.mutex().ok_or(GlobalError::Uninitialized)?)
+            .map_err(|_| GlobalError::Poisoned)?;
+        self.guard(guard).ok_or(GlobalError::Uninitialized)
+    }
+
+    /// Lock two globals and acquire the objects used to access
+    /// them. The globals are always locked in `id()` order,
+    /// whichever is `self`, so two threads fetching the same
+    /// pair cannot deadlock against each other.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`. In particular, fetching a global with
+    /// itself panics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     NAMES: Vec<&'static str> = Vec::new();
+    ///     COUNT: usize = 0;
+    /// }
+    ///
+    /// let t = std::thread::spawn(|| {
+    ///     let (mut count, mut names) = COUNT.fetch2(&NAMES);
+    ///     names.push("a");
+    ///     *count += 1;
+    /// });
+    /// {
+    ///     let (mut names, mut count) = NAMES.fetch2(&COUNT);
+    ///     names.push("b");
+    ///     *count += 1;
+    /// }
+    /// t.join().unwrap();
+    /// let (names, count) = NAMES.fetch2(&COUNT);
+    /// assert_eq!(names.len(), *count);
+    /// ```
+    #[track_caller]
+    pub fn fetch2<'a, U: 'static>(
+        &'a self,
+        other: &'a Global<U>,
+    ) -> (GlobalGuard<'a, T>, GlobalGuard<'a, U>) {
+        if self.id() < other.id() {
+            let a = self.fetch();
+            (a, other.fetch())
+        } else {
+            let b = other.fetch();
+            (self.fetch(), b)
+        }
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// even if the underlying mutex has been poisoned by a
+    /// thread panicking while holding the global.
+    ///
+    /// The panic that poisoned the global may have left its
+    /// value half-updated: the caller is responsible for
+    /// checking that it is still consistent. The global stays
+    /// poisoned until `clear_poison()` is called.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 0);
+    /// let _ = std::thread::spawn(|| {
+    ///     let _x = X.fetch();
+    ///     panic!("poison the global");
+    /// })
+    /// .join();
+    /// assert_eq!(*X.fetch_ignore_poison(), 0);
+    /// X.clear_poison();
+    /// assert_eq!(*X.fetch(), 0);
+    /// ```
+    #[track_caller]
+    pub fn fetch_ignore_poison(&self) -> GlobalGuard<'_, T> {
+        self.holder.check();
+        let guard = self
+            .lock(self.or_uninitialized(self.mutex()))
+            .unwrap_or_else(PoisonError::into_inner);
+        self.or_uninitialized(self.guard(guard))
+    }
+
+    /// Report whether a thread has panicked while holding the
+    /// global, poisoning its lock so that `fetch()` will
+    /// panic. An uninitialized global is not poisoned. Locks
+    /// are never poisoned with the `parking_lot` feature or
+    /// without the `std` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert!(!X.is_poisoned());
+    /// init!(X = 0);
+    /// let _ = std::thread::spawn(|| {
+    ///     let _x = X.fetch();
+    ///     panic!("poison the global");
+    /// })
+    /// .join();
+    /// if X.is_poisoned() {
+    ///     X.clear_poison();
+    /// }
+    /// assert!(!X.is_poisoned());
+    /// assert_eq!(*X.fetch(), 0);
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        self.cell.get().is_some_and(|m| m.is_poisoned())
+    }
+
+    /// Clear the poisoned state of a global, so that `fetch()`
+    /// will succeed again. Does nothing if the global is not
+    /// poisoned. See `fetch_ignore_poison()` for caveats.
+    pub fn clear_poison(&self) {
+        if let Some(m) = self.cell.get() {
+            m.clear_poison();
+        }
+    }
+
+    /// Get a mutable reference to the value of a global
+    /// without locking it, since `&mut self` guarantees
+    /// exclusive access. Returns `None` if the global has not
+    /// yet been initialized. Poisoning is ignored, as by
+    /// `fetch_ignore_poison()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// let mut x: Global<usize> = Global::new();
+    ///
+    /// assert!(x.get_mut().is_none());
+    /// x.init(1);
+    /// *x.get_mut().unwrap() += 1;
+    /// assert_eq!(*x.fetch(), 2);
+    /// ```
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let lazy = self.lazy;
+        if lazy.is_some() {
+            self.cell.get_or_init(|| Mutex::new(None));
+        }
+        let value = self
+            .cell
+            .get_mut()?
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if value.is_none() {
+            if let Some(f) = lazy {
+                *value = Some(f());
+            }
+        }
+        value.as_mut()
+    }
+
+    /// Consume a global, returning its value if it was
+    /// initialized. This is useful for a `Global` that is a
+    /// field of some other value rather than a `static`.
+    /// Poisoning is ignored, as by `fetch_ignore_poison()`.
+    /// The lazy initializer of a global that was never
+    /// fetched is not run, so it gives `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// let x: Global<Vec<usize>> = Global::new();
+    /// assert_eq!(x.into_value(), None);
+    ///
+    /// let x = Global::new();
+    /// x.init(vec![1]);
+    /// x.fetch().push(2);
+    /// assert_eq!(x.into_value(), Some(vec![1, 2]));
+    ///
+    /// let x = Global::new_lazy(|| 3);
+    /// assert_eq!(x.into_value(), None);
+    /// ```
+    pub fn into_value(self) -> Option<T> {
+        self.cell
+            .into_inner()
+            .and_then(|m| m.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// without panicking. See `try_fetch!()` for the macro
+    /// normally used here.
+    ///
+    /// Returns `None` if the global has not yet been
+    /// initialized, if the current thread already holds the
+    /// global, or if the underlying mutex has been poisoned.
+    #[track_caller]
+    #[must_use = "the global may not have been fetched"]
+    pub fn try_fetch(&self) -> Option<GlobalGuard<'_, T>> {
+        if self.holder.is_current() {
+            return None;
+        }
+        self.guard(self.lock(self.mutex()?).ok()?)
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// without blocking.
+    ///
+    /// Returns `None` if the lock is currently held. This is
+    /// about contention only: unlike `try_fetch()`, an
+    /// uninitialized global still panics.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 0);
+    /// let x = X.try_fetch_now().unwrap();
+    /// let t = std::thread::spawn(|| X.try_fetch_now().is_none());
+    /// assert!(t.join().unwrap());
+    /// drop(x);
+    /// ```
+    #[track_caller]
+    #[must_use = "the global may not have been fetched"]
+    pub fn try_fetch_now(&self) -> Option<GlobalGuard<'_, T>> {
+        match self.try_lock(self.or_uninitialized(self.mutex())) {
+            Ok(guard) => Some(self.or_uninitialized(self.guard(guard))),
+            Err(TryLockError::Poisoned(_)) => panic!("global lock poisoned"),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// Lock a global from an async function, waiting for the
+    /// lock without blocking the executor thread. Only lock
+    /// acquisition is awaited: the guard is the same as from
+    /// `fetch()`. Only available with the `async` feature.
+    ///
+    /// The guard cannot be sent between threads, so a future
+    /// that holds it across an `.await` cannot be spawned on a
+    /// multithreaded executor. Drop the guard before awaiting.
+    ///
+    /// # Panics
+    ///
+    /// See `poll_fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize> = Vec::new();
+    /// }
+    ///
+    /// async fn record(x: usize) {
+    ///     X.fetch_async().await.push(x);
+    /// }
+    ///
+    /// futures::executor::block_on(async {
+    ///     record(1).await;
+    ///     record(2).await;
+    /// });
+    /// assert_eq!(*X.fetch(), [1, 2]);
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn fetch_async(&self) -> FetchFuture<'_, T> {
+        FetchFuture { global: self }
+    }
+
+    /// Try to lock a global from an async task, for use in
+    /// implementing futures. Returns `Poll::Ready` with the
+    /// guard if the lock is free. Otherwise the task's waker is
+    /// registered, to be woken when the global is next
+    /// unlocked, and `Poll::Pending` is returned; the executor
+    /// thread is never blocked. Only available with the
+    /// `async` feature.
+    ///
+    /// Tasks on one thread may legitimately contend for a
+    /// global, so unlike `fetch()` this does not check whether
+    /// the current thread already holds it. A task that polls
+    /// for a global it holds itself will wait forever.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not been initialized or its
+    /// lock is poisoned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::task::{Context, Poll, Wake, Waker};
+    /// use ugly_global::*;
+    ///
+    /// struct Flag(AtomicBool);
+    /// impl Wake for Flag {
+    ///     fn wake(self: Arc<Self>) {
+    ///         self.0.store(true, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// global_vars! {
+    ///     X: usize = 0;
+    /// }
+    ///
+    /// let flag = Arc::new(Flag(AtomicBool::new(false)));
+    /// let waker = Waker::from(flag.clone());
+    /// let mut cx = Context::from_waker(&waker);
+    ///
+    /// let x = X.fetch();
+    /// assert!(X.poll_fetch(&mut cx).is_pending());
+    /// drop(x);
+    /// assert!(flag.0.load(Ordering::SeqCst));
+    /// match X.poll_fetch(&mut cx) {
+    ///     Poll::Ready(mut x) => *x += 1,
+    ///     Poll::Pending => panic!("global still locked"),
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    #[track_caller]
+    pub fn poll_fetch(&self, cx: &mut Context<'_>) -> Poll<GlobalGuard<'_, T>> {
+        let m = self.or_uninitialized(self.mutex());
+        let mut registered = false;
+        loop {
+            match self.try_lock(m) {
+                Ok(guard) => return Poll::Ready(self.or_uninitialized(self.guard(guard))),
+                Err(TryLockError::Poisoned(_)) => panic!("global lock poisoned"),
+                Err(TryLockError::WouldBlock) if registered => return Poll::Pending,
+                Err(TryLockError::WouldBlock) => {
+                    // Try again once registered, in case the
+                    // global was unlocked in the meantime.
+                    self.wakers.register(cx.waker());
+                    registered = true;
+                }
+            }
+        }
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// giving up if the lock cannot be acquired within `dur`.
+    ///
+    /// Returns `None` on timeout.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 0);
+    /// let x = X.fetch_timeout(Duration::from_millis(10)).unwrap();
+    /// let t = std::thread::spawn(|| X.fetch_timeout(Duration::from_millis(10)).is_none());
+    /// assert!(t.join().unwrap());
+    /// drop(x);
+    /// ```
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn fetch_timeout(&self, dur: Duration) -> Option<GlobalGuard<'_, T>> {
+        let m = self.or_uninitialized(self.mutex());
+        match self.try_lock_with(m, |m| m.try_lock_for(dur)) {
+            Ok(guard) => Some(self.or_uninitialized(self.guard(guard))),
+            Err(TryLockError::Poisoned(_)) => panic!("global lock poisoned"),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// first initializing the global with the result of `f` if
+    /// it has not yet been initialized. `f` will be run at most
+    /// once, even if several threads race to access the global.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the underlying mutex gets poisoned (should
+    /// not happen). Will panic if the current thread already
+    /// holds the global, rather than deadlocking, including
+    /// from within `f`:
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// X.get_or_init(|| X.get_copy() + 1);
+    /// ```
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize>;
+    /// }
+    ///
+    /// X.get_or_init(Vec::new).push(1);
+    /// X.get_or_init(|| unreachable!()).push(2);
+    /// assert_eq!(*X.fetch(), [1, 2]);
+    /// ```
+    #[track_caller]
+    pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> GlobalGuard<'_, T> {
+        self.holder.check();
+        let mut guard = self
+            .lock(self.cell.get_or_init(|| Mutex::new(None)))
+            .expect("global lock poisoned");
+        if guard.is_none() {
+            // `f` runs under the lock, so must not fetch the
+            // global itself.
+            let held = self.holder.hold();
+            *guard = Some(f());
+            drop(held);
+            self.notify_initialized();
+            self.trace_init();
+        }
+        GlobalGuard::new(guard, self).expect("global uninitialized")
+    }
+
+    /// Lock a global and acquire the object used to access it,
+    /// first initializing the global with the default value of
+    /// its type if it has not yet been initialized.
+    ///
+    /// # Panics
+    ///
+    /// See `get_or_init()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     COUNT: usize;
+    /// }
+    ///
+    /// *COUNT.fetch_or_init_default() += 1;
+    /// *COUNT.fetch_or_init_default() += 1;
+    /// assert_eq!(*COUNT.fetch(), 2);
+    /// ```
+    #[track_caller]
+    pub fn fetch_or_init_default(&self) -> GlobalGuard<'_, T>
+    where
+        T: Default,
+    {
+        self.get_or_init(T::default)
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value.  See `init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// Will panic on initialization failure; for example on an attempt
+    /// to reinitialize a variable. The panic message names the
+    /// global if it has a name, as globals declared with
+    /// `global_vars!` do:
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 0);
+    /// // Panics with "re-initialization of global `X`".
+    /// init!(X = 1);
+    /// ```
+    ///
+    /// In debug builds, will also panic if an earlier
+    /// `fetch()` panicked because the global was not yet
+    /// initialized: this usually means that `init!()` is run
+    /// too late. `deinit()` and `reset()` forget such a fetch.
+    ///
+    /// ```should_panic
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// let _ = std::thread::spawn(|| *X.fetch()).join();
+    /// // Panics with "init() called after first fetch()".
+    /// init!(X = 0);
+    /// ```
+    #[track_caller]
+    pub fn init(&self, v: T) {
+        self.check_fetched_early();
+        if self.try_init(v).is_err() {
+            match self.name {
+                Some(name) => panic!("re-initialization of global `{}`", name),
+                None => panic!("initialization failed"),
+            }
+        }
+    }
+
+    /// Initialize a global reference to contain the result of
+    /// `f`. This is an explicit one-time setup call for values
+    /// that are expensive to build; see `get_or_init()` for
+    /// initialization on first access instead. See
+    /// `init_from!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// See `init()`.
+    #[track_caller]
+    pub fn init_from<F: FnOnce() -> T>(&self, f: F) {
+        self.init(f());
+    }
+
+    /// Initialize a global reference to contain the result of
+    /// the fallible function `f`. If `f` fails its error is
+    /// returned and the global is left uninitialized, so that
+    /// initialization can be retried.
+    ///
+    /// # Panics
+    ///
+    /// See `init()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     PORT: u16;
+    /// }
+    ///
+    /// assert!(PORT.try_init_from(|| "http".parse()).is_err());
+    /// assert!(!PORT.is_initialized());
+    /// assert!(PORT.try_init_from(|| "80".parse()).is_ok());
+    /// assert_eq!(*PORT.fetch(), 80);
+    /// ```
+    #[track_caller]
+    pub fn try_init_from<E, F: FnOnce() -> Result<T, E>>(&self, f: F) -> Result<(), E> {
+        self.init(f()?);
+        Ok(())
+    }
+
+    /// Initialize a global reference to contain the value of
+    /// the environment variable `var`, parsed with `FromStr`.
+    /// If the variable is unset or does not parse, the error is
+    /// returned and the global is left uninitialized. See
+    /// `env_init!()` for the macro normally used here.
+    ///
+    /// # Panics
+    ///
+    /// See `init()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     PORT: u16;
+    /// }
+    ///
+    /// std::env::set_var("EXAMPLE_PORT", "http");
+    /// assert!(matches!(PORT.try_init_from_env("EXAMPLE_PORT"), Err(EnvError::Parse(_))));
+    /// std::env::set_var("EXAMPLE_PORT", "80");
+    /// assert_eq!(PORT.try_init_from_env("EXAMPLE_PORT"), Ok(()));
+    /// assert_eq!(*PORT.fetch(), 80);
+    /// ```
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_init_from_env(&self, var: &str) -> Result<(), EnvError<T::Err>>
+    where
+        T: core::str::FromStr,
+    {
+        let s = std::env::var(var).map_err(EnvError::Var)?;
+        self.init(s.parse().map_err(EnvError::Parse)?);
+        Ok(())
+    }
+
+    /// Initialize a global reference to contain the value of
+    /// the environment variable `var`, parsed with `FromStr`,
+    /// or `default` if the variable is unset or does not
+    /// parse. See `env_init!()` for the macro normally used
+    /// here.
+    ///
+    /// # Panics
+    ///
+    /// See `init()`.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn init_from_env_or(&self, var: &str, default: T)
+    where
+        T: core::str::FromStr,
+    {
+        match std::env::var(var).ok().and_then(|s| s.parse().ok()) {
+            Some(v) => self.init(v),
+            None => self.init(default),
+        }
+    }
+
+    /// Initialize a global reference to contain the default
+    /// value of its type. See `init_default!()` for the macro
+    /// normally used here.
+    ///
+    /// # Panics
+    ///
+    /// See `init()`.
+    #[track_caller]
+    pub fn init_default(&self)
+    where
+        T: Default,
+    {
+        self.init(T::default());
+    }
+
+    /// Initialize a global reference to contain an initial
+    /// value, without panicking.
+    ///
+    /// Returns `Err(v)`, handing the value back, if the global
+    /// has already been initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert_eq!(X.try_init(1), Ok(()));
+    /// assert_eq!(X.try_init(2), Err(2));
+    /// assert_eq!(*X.fetch(), 1);
+    /// ```
+    ///
+    /// Unlike `init()`, this does not panic after an earlier
+    /// fetch of the uninitialized global:
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert!(std::panic::catch_unwind(|| X.get_copy()).is_err());
+    /// assert_eq!(X.try_init(1), Ok(()));
+    /// ```
+    #[track_caller]
+    pub fn try_init(&self, v: T) -> Result<(), T> {
+        let _wake = WakeOnDrop::new(&self.wakers);
+        if self.holder.is_current() {
+            return Err(v);
+        }
+        let mut guard = self.lock_cell();
+        if guard.is_some() {
+            return Err(v);
+        }
+        *guard = Some(v);
+        self.notify_initialized();
+        drop(guard);
+        self.trace_init();
+        Ok(())
+    }
+
+    /// Initialize a global if it has not yet been initialized,
+    /// returning whether it was. The value is dropped if the
+    /// global already had one. This suits races where several
+    /// threads may seed the same global and the first wins; use
+    /// `try_init()` to get the value back instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     SEED: u64;
+    /// }
+    ///
+    /// let seeders: Vec<_> = (0..4)
+    ///     .map(|i| std::thread::spawn(move || SEED.set_if_uninitialized(i)))
+    ///     .collect();
+    /// let won: Vec<bool> = seeders.into_iter().map(|t| t.join().unwrap()).collect();
+    /// assert_eq!(won.iter().filter(|&&w| w).count(), 1);
+    /// assert!(won[SEED.get_copy() as usize]);
+    /// ```
+    #[track_caller]
+    pub fn set_if_uninitialized(&self, v: T) -> bool {
+        self.try_init(v).is_ok()
+    }
+
+    /// Set the value of a global, initializing it if it has
+    /// not yet been initialized and overwriting its value
+    /// otherwise. Use `init()` instead when a second
+    /// initialization would be a bug.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// X.set(1);
+    /// X.set(2);
+    /// assert_eq!(*X.fetch(), 2);
+    /// ```
+    #[track_caller]
+    pub fn set(&self, v: T) {
+        let mut guard = self.lock_cell();
+        let initializing = guard.is_none();
+        *guard = Some(v);
+        if initializing {
+            self.trace_init();
+        }
+        if let Some(guard) = GlobalGuard::new(guard, self) {
+            self.changed(&guard);
+        }
+        self.notify_initialized();
+    }
+
+    /// Block until the global has been initialized, for
+    /// example by another thread calling `init()`. Returns at
+    /// once for a global with an initializer, which will be
+    /// initialized on access.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     CONFIG: String;
+    /// }
+    ///
+    /// let worker = std::thread::spawn(|| {
+    ///     CONFIG.wait_until_initialized();
+    ///     CONFIG.fetch().len()
+    /// });
+    /// std::thread::sleep(std::time::Duration::from_millis(10));
+    /// init!(CONFIG = "verbose".to_string());
+    /// assert_eq!(worker.join().unwrap(), 7);
+    /// ```
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn wait_until_initialized(&self) {
+        let _wake = WakeOnDrop::new(&self.wakers);
+        if self.lazy.is_some() {
+            return;
+        }
+        let mut guard = self.lock_cell();
+        while guard.is_none() {
+            guard = self
+                .ready
+                .wait(guard)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Replace the value of a global, returning the old value.
+    /// The exchange is atomic: no other thread sees the global
+    /// between the old value leaving and the new one arriving.
+    /// `swap_value()` is the same method under another name;
+    /// `swap()` instead exchanges the values of two globals.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 1);
+    /// assert_eq!(X.replace(2), 1);
+    /// assert_eq!(*X.fetch(), 2);
+    /// ```
+    #[track_caller]
+    pub fn replace(&self, v: T) -> T {
+        let mut guard = self.fetch();
+        let old = core::mem::replace(&mut *guard, v);
+        self.changed(&guard);
+        old
+    }
+
+    /// Swap `v` into a global, returning the value it held.
+    /// This is `replace()` under another name.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     TOKEN: u32 = 1;
+    /// }
+    ///
+    /// assert_eq!(TOKEN.swap_value(2), 1);
+    /// assert_eq!(TOKEN.get_copy(), 2);
+    /// ```
+    #[track_caller]
+    pub fn swap_value(&self, v: T) -> T {
+        self.replace(v)
+    }
+
+    /// Replace the value of a global with `new`, but only if
+    /// it currently equals `expected`. Returns whether the
+    /// value was replaced. The global stays locked between
+    /// the comparison and the replacement.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// #[derive(PartialEq)]
+    /// enum State { Idle, Running, Done }
+    /// global_vars! {
+    ///     STATE: State = State::Idle;
+    /// }
+    ///
+    /// assert!(STATE.compare_and_set(&State::Idle, State::Running));
+    /// assert!(!STATE.compare_and_set(&State::Idle, State::Running));
+    /// assert!(STATE.compare_and_set(&State::Running, State::Done));
+    /// ```
+    #[track_caller]
+    pub fn compare_and_set(&self, expected: &T, new: T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut guard = self.fetch();
+        if *guard == *expected {
+            *guard = new;
+            self.changed(&guard);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Temporarily replace the value of a global, restoring
+    /// the old value when the returned guard is dropped. This
+    /// is mostly useful for injecting test values. See
+    /// `scoped_override!()` for the macro normally used here.
+    ///
+    /// Restoring never panics, since the guard may be dropped
+    /// while a failed test unwinds. Poisoning is ignored, and a
+    /// global deinitialized in the meantime gets the old value
+    /// back. If the dropping thread still holds the global,
+    /// the old value is dropped instead of restored.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     MODE: &'static str = "live";
+    /// }
+    ///
+    /// {
+    ///     let _mode = MODE.scoped_override("test");
+    ///     assert_eq!(MODE.get_copy(), "test");
+    ///     MODE.deinit();
+    /// }
+    /// assert_eq!(MODE.get_copy(), "live");
+    ///
+    /// let mode = MODE.scoped_override("test");
+    /// let held = MODE.fetch();
+    /// drop(mode);
+    /// assert_eq!(*held, "test");
+    /// ```
+    #[track_caller]
+    pub fn scoped_override(&self, v: T) -> OverrideGuard<'_, T> {
+        OverrideGuard {
+            global: self,
+            old: Some(self.replace(v)),
+        }
+    }
+
+    /// Exchange the values of two globals. Both are locked, in
+    /// `id()` order so that concurrent swaps cannot deadlock.
+    /// Swapping a global with itself does nothing.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`. Both globals must be initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     FRONT: Vec<u8> = vec![1];
+    ///     BACK: Vec<u8> = vec![2];
+    /// }
+    ///
+    /// FRONT.swap(&BACK);
+    /// assert_eq!(*FRONT.fetch(), [2]);
+    /// assert_eq!(*BACK.fetch(), [1]);
+    /// ```
+    #[track_caller]
+    pub fn swap(&self, other: &Global<T>) {
+        if core::ptr::eq(self, other) {
+            return;
+        }
+        let (mut a, mut b) = self.fetch2(other);
+        core::mem::swap(&mut *a, &mut *b);
+        self.changed(&a);
+        other.changed(&b);
+    }
+
+    /// Take the value of a global, leaving the default value
+    /// in its place. The global must still have been
+    /// initialized first.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     EVENTS: Vec<&'static str>;
+    /// }
+    ///
+    /// init!(EVENTS = vec!["a", "b"]);
+    /// assert_eq!(EVENTS.take(), ["a", "b"]);
+    /// assert!(EVENTS.fetch().is_empty());
+    /// ```
+    #[track_caller]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        let mut guard = self.fetch();
+        let v = core::mem::take(&mut *guard);
+        self.changed(&guard);
+        v
+    }
+
+    /// Reset a global to the default value, returning the
+    /// value it held. This is `take()` under another name, for
+    /// routines that periodically flush and reset counters:
+    /// no update can fall between the read and the reset.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     HITS: HashMap<&'static str, u64> = HashMap::new();
+    /// }
+    ///
+    /// *HITS.fetch().entry("/index").or_default() += 1;
+    /// let flushed = HITS.swap_with_default();
+    /// assert_eq!(flushed["/index"], 1);
+    /// assert!(HITS.fetch().is_empty());
+    /// ```
+    #[track_caller]
+    pub fn swap_with_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.take()
+    }
+
+    /// Remove the value of a global, returning it if the
+    /// global was initialized and leaving the global
+    /// uninitialized. The caller decides when the value is
+    /// dropped, which is useful for flushing or closing
+    /// resources at shutdown. A global with an initializer
+    /// will be initialized afresh on its next access.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     LOG: Vec<String>;
+    /// }
+    ///
+    /// assert_eq!(LOG.deinit(), None);
+    /// init!(LOG = vec!["done".to_string()]);
+    /// assert_eq!(LOG.deinit().unwrap(), ["done"]);
+    /// assert!(!LOG.is_initialized());
+    ///
+    /// // A fetch of the uninitialized global is forgotten.
+    /// assert!(std::panic::catch_unwind(|| LOG.fetch().len()).is_err());
+    /// LOG.deinit();
+    /// init!(LOG = Vec::new());
+    /// ```
+    #[track_caller]
+    pub fn deinit(&self) -> Option<T> {
+        let _wake = WakeOnDrop::new(&self.wakers);
+        self.holder.check();
+        self.clear_fetched_early();
+        let m = self.cell.get()?;
+        self.lock(m).unwrap_or_else(PoisonError::into_inner).take()
+    }
+
+    /// Lock a global, run `f` on its value, and release the
+    /// lock before returning the result of `f`. This keeps the
+    /// critical section short and obvious.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 1);
+    /// let x = X.with(|x| {
+    ///     *x += 1;
+    ///     *x
+    /// });
+    /// assert_eq!(x, 2);
+    /// ```
+    #[track_caller]
+    pub fn with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        let mut guard = self.fetch();
+        let r = f(&mut guard);
+        self.changed(&guard);
+        r
+    }
+
+    /// Lock a global, run `f` on its value, and release the
+    /// lock before returning the result of `f`, giving up if
+    /// the lock cannot be acquired within `dur`. See
+    /// `fetch_timeout()`.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize = 0;
+    /// }
+    ///
+    /// let wait = Duration::from_millis(10);
+    /// assert_eq!(X.try_with_timeout(wait, |x| *x + 1), Ok(1));
+    /// let x = X.fetch();
+    /// let t = std::thread::spawn(move || X.try_with_timeout(wait, |x| *x + 1));
+    /// assert_eq!(t.join().unwrap(), Err(TimeoutError));
+    /// drop(x);
+    /// ```
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn try_with_timeout<R, F: FnOnce(&mut T) -> R>(
+        &self,
+        dur: Duration,
+        f: F,
+    ) -> Result<R, TimeoutError> {
+        let mut guard = self.fetch_timeout(dur).ok_or(TimeoutError)?;
+        let r = f(&mut guard);
+        self.changed(&guard);
+        Ok(r)
+    }
+
+    /// Lock a global, run `f` on its value, and release the
+    /// lock before returning the result of `f`. `f` is given
+    /// `Ok(value)` normally, or `Err(value)` if the global has
+    /// been poisoned by a thread panicking while holding it,
+    /// so that it can repair the value. The global stays
+    /// poisoned until `clear_poison()` is called.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     TOTALS: Vec<usize> = vec![0];
+    /// }
+    ///
+    /// let _ = std::thread::spawn(|| {
+    ///     let mut totals = TOTALS.fetch();
+    ///     totals.push(1);
+    ///     panic!("poison the global");
+    /// })
+    /// .join();
+    /// TOTALS.with_poisoned(|totals| match totals {
+    ///     Ok(_) => (),
+    ///     Err(totals) => {
+    ///         totals.truncate(1);
+    ///         TOTALS.clear_poison();
+    ///     }
+    /// });
+    /// assert!(!TOTALS.is_poisoned());
+    /// ```
+    #[track_caller]
+    pub fn with_poisoned<R, F: FnOnce(Result<&mut T, &mut T>) -> R>(&self, f: F) -> R {
+        self.holder.check();
+        let (guard, poisoned) = match self.lock(self.or_uninitialized(self.mutex())) {
+            Ok(guard) => (guard, false),
+            Err(e) => (e.into_inner(), true),
+        };
+        let mut guard = self.or_uninitialized(self.guard(guard));
+        if poisoned {
+            f(Err(&mut guard))
+        } else {
+            f(Ok(&mut guard))
+        }
+    }
+
+    /// Lock a global, run `f` on its value, and release the
+    /// lock before returning the result of `f`, even if the
+    /// global has been poisoned by a thread panicking while
+    /// holding it. The global stays poisoned.
+    ///
+    /// **The value may be inconsistent.** The panic that
+    /// poisoned the global may have struck halfway through an
+    /// update, and `f` cannot tell. Use this only where that
+    /// cannot matter, for example because every update is a
+    /// single assignment, or where a half-updated value is
+    /// better than none; use `with_poisoned()` to find out
+    /// whether the global was poisoned.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the global has not yet been initialized.
+    /// Will panic if the current thread already holds the
+    /// global, rather than deadlocking.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     LAST_ERROR: Option<String> = None;
+    /// }
+    ///
+    /// let _ = std::thread::spawn(|| {
+    ///     let _last = LAST_ERROR.fetch();
+    ///     panic!("poison the global");
+    /// })
+    /// .join();
+    /// LAST_ERROR.with_recover(|e| *e = Some("worker died".to_string()));
+    /// assert_eq!(LAST_ERROR.with_recover(|e| e.clone()).unwrap(), "worker died");
+    /// ```
+    #[track_caller]
+    pub fn with_recover<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        f(&mut self.fetch_ignore_poison())
+    }
+
+    /// Register a callback to be run with the new value of a
+    /// global each time it is changed through `set()`,
+    /// `replace()`, `swap_value()`, `swap()`,
+    /// `compare_and_set()`, `replace_with()`, `take()`,
+    /// `swap_with_default()`, `with()`, `update()`,
+    /// `try_update()`, `try_with_timeout()` or
+    /// `modify_and_get()`, or by `scoped_override()` and its
+    /// restoring of the old value. Changes made through a guard
+    /// from `fetch()` are not seen, nor are initialization by
+    /// `init()` and the like and removal by `deinit()`. Only
+    /// available with the `std` feature.
+    ///
+    /// Callbacks run in the order registered, on the thread
+    /// that made the change, while the global is still locked.
+    /// A callback must not access the global or register
+    /// another callback on it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     CONFIG: Vec<String> = Vec::new();
+    /// }
+    ///
+    /// static CHANGES: AtomicUsize = AtomicUsize::new(0);
+    /// CONFIG.on_change(|c| {
+    ///     CHANGES.fetch_add(1, Ordering::SeqCst);
+    ///     println!("config now has {} entries", c.len());
+    /// });
+    ///
+    /// CONFIG.update(|c| c.push("verbose".to_string()));
+    /// CONFIG.set(Vec::new());
+    /// CONFIG.fetch().push("unseen".to_string());
+    /// assert_eq!(CHANGES.load(Ordering::SeqCst), 2);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn on_change<F: Fn(&T) + Send + Sync + 'static>(&self, f: F) {
+        self.observers
+            .get_or_init(|| std::sync::Mutex::new(Vec::new()))
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(f));
+    }
+
+    /// Lock a global, apply the mutation `f` to its value, and
+    /// release the lock. See `update!()` for the macro
+    /// normally used here.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    #[track_caller]
+    pub fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        let mut guard = self.fetch();
+        f(&mut guard);
+        self.changed(&guard);
+    }
+
+    /// Apply the mutation `f` to the value of a global if its
+    /// lock is free, returning whether it was. Unlike
+    /// `update()`, this never waits, so low-priority work can
+    /// back off when the global is busy.
+    ///
+    /// # Panics
+    ///
+    /// See `try_fetch_now()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     CACHE: Vec<Option<u32>> = vec![Some(1), None, Some(2)];
+    /// }
+    ///
+    /// let busy = CACHE.fetch();
+    /// assert!(!CACHE.try_update(|c| c.retain(Option::is_some)));
+    /// drop(busy);
+    /// assert!(CACHE.try_update(|c| c.retain(Option::is_some)));
+    /// assert_eq!(CACHE.fetch().len(), 2);
+    /// ```
+    #[track_caller]
+    pub fn try_update<F: FnOnce(&mut T)>(&self, f: F) -> bool {
+        match self.try_fetch_now() {
+            Some(mut guard) => {
+                f(&mut guard);
+                self.changed(&guard);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Lock a global and replace its value with the result of
+    /// `f` on the old value, for transformations that consume
+    /// the old value. While `f` runs the global holds
+    /// `T::default()`, which it keeps if `f` panics.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// #[derive(Default, Debug, PartialEq)]
+    /// enum State {
+    ///     #[default]
+    ///     Idle,
+    ///     Running(Vec<u32>),
+    /// }
+    /// global_vars! {
+    ///     STATE: State = State::Idle;
+    /// }
+    ///
+    /// STATE.replace_with(|s| match s {
+    ///     State::Idle => State::Running(vec![1]),
+    ///     running => running,
+    /// });
+    /// assert_eq!(*STATE.fetch(), State::Running(vec![1]));
+    /// ```
+    #[track_caller]
+    pub fn replace_with<F: FnOnce(T) -> T>(&self, f: F)
+    where
+        T: Default,
+    {
+        let mut guard = self.fetch();
+        let old = core::mem::take(&mut *guard);
+        *guard = f(old);
+        self.changed(&guard);
+    }
+
+    /// Lock a global, apply the mutation `f` to its value, and
+    /// release the lock, returning a clone of the new value.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     COUNT: usize = 0;
+    /// }
+    ///
+    /// assert_eq!(COUNT.modify_and_get(|c| *c += 1), 1);
+    /// assert_eq!(COUNT.modify_and_get(|c| *c += 1), 2);
+    /// ```
+    #[track_caller]
+    pub fn modify_and_get<F: FnOnce(&mut T)>(&self, f: F) -> T
+    where
+        T: Clone,
+    {
+        let mut guard = self.fetch();
+        f(&mut guard);
+        self.changed(&guard);
+        guard.clone()
+    }
+
+    /// Lock a global, run `f` on a shared reference to its
+    /// value, and release the lock before returning the result
+    /// of `f`. The lock is still exclusive, but this documents
+    /// that the value is only read; `RwGlobal::with_ref()`
+    /// takes a shared read lock instead.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize>;
+    /// }
+    ///
+    /// init!(X = vec![1, 2, 3]);
+    /// assert_eq!(X.with_ref(|x| x.len()), 3);
+    /// ```
+    #[track_caller]
+    pub fn with_ref<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        f(&self.fetch())
+    }
+
+    /// Read a projection of the value of a global, such as the
+    /// length of a collection, holding the lock only while `f`
+    /// runs. This is `with_ref()` under another name.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     SESSIONS: HashMap<u32, String> = HashMap::new();
+    /// }
+    ///
+    /// assert!(SESSIONS.map_ref(HashMap::is_empty));
+    /// SESSIONS.fetch().insert(7, "alice".to_string());
+    /// assert_eq!(SESSIONS.map_ref(HashMap::len), 1);
+    /// assert_eq!(SESSIONS.map_ref(|s| s[&7].clone()), "alice");
+    /// ```
+    #[track_caller]
+    pub fn map_ref<R, F: FnOnce(&T) -> R>(&self, f: F) -> R {
+        self.with_ref(f)
+    }
+
+    /// Lock a global just long enough to clone its value, and
+    /// return the clone.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     NAME: String;
+    /// }
+    ///
+    /// init!(NAME = "x".to_string());
+    /// assert_eq!(NAME.get_cloned(), "x");
+    /// ```
+    #[track_caller]
+    pub fn get_cloned(&self) -> T
+    where
+        T: Clone,
+    {
+        self.fetch().clone()
+    }
+
+    /// Lock a global just long enough to clone its value, and
+    /// return the clone, or return `default` if the global is
+    /// not available as for `try_fetch()`. Never panics: the
+    /// lock order is not checked even with `lock_order`.
+    /// Unlike `try_fetch()`, a lazy initializer is not run:
+    /// `default` is returned instead, and the global stays
+    /// uninitialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     NAME: String;
+    ///     GREETING: String = "hello".to_string();
+    /// }
+    ///
+    /// assert_eq!(NAME.get_cloned_or("anon".to_string()), "anon");
+    /// assert!(!NAME.is_initialized());
+    /// init!(NAME = "x".to_string());
+    /// assert_eq!(NAME.get_cloned_or("anon".to_string()), "x");
+    ///
+    /// assert_eq!(GREETING.get_cloned_or("hi".to_string()), "hi");
+    /// assert_eq!(*GREETING.fetch(), "hello");
+    /// ```
+    pub fn get_cloned_or(&self, default: T) -> T
+    where
+        T: Clone,
+    {
+        if self.holder.is_current() {
+            return default;
+        }
+        let m = match self.cell.get() {
+            Some(m) => m,
+            None => return default,
+        };
+        let _wake = WakeOnDrop::new(&self.wakers);
+        match self.lock_unchecked(m) {
+            Ok(guard) => guard.as_ref().cloned().unwrap_or(default),
+            Err(_) => default,
+        }
+    }
+
+    /// Lock a global just long enough to compare its value
+    /// with `other`. This is mostly useful in test
+    /// assertions.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: Vec<usize>;
+    /// }
+    ///
+    /// init!(X = vec![1]);
+    /// assert!(X.eq_value(&vec![1]));
+    /// assert!(!X.eq_value(&vec![2]));
+    /// ```
+    #[track_caller]
+    pub fn eq_value(&self, other: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        *self.fetch() == *other
+    }
+
+    /// Lock a global just long enough to copy its value, and
+    /// return the copy.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 1);
+    /// assert_eq!(X.get_copy(), 1);
+    /// ```
+    #[track_caller]
+    pub fn get_copy(&self) -> T
+    where
+        T: Copy,
+    {
+        *self.fetch()
+    }
+
+    /// Read a `Copy` global without keeping a guard. This is
+    /// `get_copy()` under another name: the global is locked
+    /// only long enough to copy the value out. The result is a
+    /// snapshot of the value at the time of the call, not a
+    /// live view, and may be stale by the time it is used.
+    ///
+    /// # Panics
+    ///
+    /// See `fetch()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     LIMIT: u32 = 10;
+    /// }
+    ///
+    /// let limit = LIMIT.get_unlocked();
+    /// LIMIT.set(20);
+    /// assert_eq!(limit, 10);
+    /// assert_eq!(LIMIT.get_unlocked(), 20);
+    /// ```
+    #[track_caller]
+    pub fn get_unlocked(&self) -> T
+    where
+        T: Copy,
+    {
+        self.get_copy()
+    }
+
+    /// Run `f` on the value of a global without waiting for
+    /// its lock, for diagnostics such as panic hooks. `f` is
+    /// given `None` if the global is uninitialized or its lock
+    /// is held, including by the current thread. Poisoning is
+    /// ignored, and a lazy initializer is not run. Like `Debug`
+    /// formatting, this never panics, so is neither counted by
+    /// `stats()` nor checked with the `lock_order` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// assert_eq!(X.inspect(|x| x.copied()), None);
+    /// init!(X = 1);
+    /// assert_eq!(X.inspect(|x| x.copied()), Some(1));
+    /// let _x = X.fetch();
+    /// assert_eq!(X.inspect(|x| x.copied()), None);
+    /// ```
+    pub fn inspect<R, F: FnOnce(Option<&T>) -> R>(&self, f: F) -> R {
+        let _wake = WakeOnDrop::new(&self.wakers);
+        let cell = match self.cell.get() {
+            Some(cell) if !self.holder.is_current() => cell,
+            _ => return f(None),
+        };
+        // Not `self.try_lock()`, as for `Debug`.
+        let guard = match cell.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return f(None),
+        };
+        f(guard.as_ref())
+    }
+
+    /// Lock a global and run `f` on its current value, or on
+    /// `None` if it is uninitialized. Unlike `checked_fetch()`,
+    /// a lazy initializer is not run. For `Serialize`.
+    #[cfg(feature = "serde")]
+    #[track_caller]
+    pub(crate) fn with_current<R, F: FnOnce(Option<&T>) -> R>(
+        &self,
+        f: F,
+    ) -> Result<R, GlobalError> {
+        self.holder.check();
+        let m = match self.cell.get() {
+            Some(m) => m,
+            None => return Ok(f(None)),
+        };
+        let _wake = WakeOnDrop::new(&self.wakers);
+        let guard = self.lock(m).map_err(|_| GlobalError::Poisoned)?;
+        Ok(f(guard.as_ref()))
+    }
+
+    /// Lock contention statistics for the global: how often
+    /// its lock has been acquired, and how long acquiring it
+    /// has taken. Every way of locking the global counts,
+    /// including `try_fetch_now()`, `fetch_timeout()` and
+    /// `poll_fetch()`; queries such as `is_initialized()`,
+    /// `inspect()` and `Debug` formatting do not. Only
+    /// available with the `metrics` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize = 0;
+    /// }
+    ///
+    /// *X.fetch() += 1;
+    /// *X.fetch() += 1;
+    /// let stats = X.stats();
+    /// assert_eq!(stats.acquisitions, 2);
+    /// assert!(stats.max_wait <= stats.total_wait);
+    ///
+    /// let wait = Duration::from_millis(20);
+    /// *X.fetch_timeout(wait).unwrap() += 1;
+    /// assert_eq!(X.stats().acquisitions, 3);
+    /// let x = X.fetch();
+    /// let t = std::thread::spawn(move || X.fetch_timeout(wait).is_none());
+    /// assert!(t.join().unwrap());
+    /// drop(x);
+    /// let stats = X.stats();
+    /// assert_eq!(stats.acquisitions, 4);
+    /// assert!(stats.max_wait >= wait);
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn stats(&self) -> GlobalStats {
+        self.metrics.stats()
+    }
+
+    /// Return a global to the uninitialized state, dropping its
+    /// value, so that it can be initialized afresh. Poisoning of
+    /// the underlying mutex is cleared. Intended for tests that
+    /// share a global; only available with the `testing`
+    /// feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ugly_global::*;
+    /// global_vars! {
+    ///     X: usize;
+    /// }
+    ///
+    /// init!(X = 1);
+    /// X.reset();
+    /// assert!(!X.is_initialized());
+    /// init!(X = 2);
+    /// assert_eq!(*X.fetch(), 2);
+    ///
+    /// // A fetch of the uninitialized global is forgotten too.
+    /// X.reset();
+    /// assert!(std::panic::catch_unwind(|| X.get_copy()).is_err());
+    /// X.reset();
+    /// init!(X = 3);
+    /// assert_eq!(*X.fetch(), 3);
+    /// ```
+    #[cfg(feature = "testing")]
+    #[track_caller]
+    pub fn reset(&self) {
+        let _wake = WakeOnDrop::new(&self.wakers);
+        self.holder.check();
+        if let Some(m) = self.cell.get() {
+            *self.lock(m).unwrap_or_else(PoisonError::into_inner) = None;
+            m.clear_poison();
+        }
+        self.clear_fetched_early();
+    }
+}
+
+/// Same as `Global::new()`, so that a `Global` can be a
+/// field of a type deriving `Default`.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// #[derive(Default)]
+/// struct Registry {
+///     names: Global<Vec<String>>,
+/// }
+///
+/// let r = Registry::default();
+/// assert!(!r.names.is_initialized());
+/// r.names.init(Vec::new());
+/// assert!(r.names.fetch().is_empty());
+/// ```
+impl<T: 'static> Default for Global<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shows the value of the global if it is initialized and
+/// its lock is free. The lock is not waited for, so a thread
+/// holding the global can safely print it.
+///
+/// # Examples
+///
+/// ```
+/// use ugly_global::*;
+/// global_vars! {
+///     X: usize;
+/// }
+///
+/// assert_eq!(format!("{:?}", X), r#"Global { name: "X", value: <uninitialized> }"#);
+/// init!(X = 1);
+/// assert_eq!(format!("{:?}", X), r#"Global { name: "X", value: 1 }"#);
+/// let _x = X.fetch();
+/// assert_eq!(format!("{:?}", X), r#"Global { name: "X", value: <locked> }"#);
+/// ```
+impl<T: fmt::Debug> fmt::Debug for Global<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Global");
+        if let Some(name) = self.name {
+            d.field("name", &name);
+        }
+        let cell = match self.cell.get() {
+            Some(cell) => cell,
+            None => return d.field("value", &format_args!("<uninitialized>")).finish(),
+        };
+        let _wake = WakeOnDrop::new(&self.wakers);
+        // Not `self.try_lock()`: formatting is used by the
+        // crash dumper's panic hook, so must not be counted or
+        // panic on a lock-order violation. Likewise `inspect()`.
+        let guard = match cell.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return d.field("value", &format_args!("<locked>")).finish()
+            }
+        };
+        match &*guard {
+            Some(v) => d.field("value", v),
+            None => d.field("value", &format_args!("<uninitialized>")),
+        }
+        .finish()
+    }
+}
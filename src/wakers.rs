@@ -0,0 +1,82 @@
+//! Queue of async tasks waiting for a `Global` to be
+//! unlocked, for `Global::poll_fetch()`. Without the `async`
+//! feature the queue is empty and waking it does nothing, so
+//! that unlocking costs nothing extra.
+
+#[cfg(feature = "async")]
+use core::sync::atomic::{fence, AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use core::task::Waker;
+#[cfg(feature = "async")]
+use std::sync::{Mutex, PoisonError};
+
+/// Tasks to wake when a global is next unlocked.
+pub(crate) struct Wakers {
+    /// Set when the queue may be nonempty, so that unlocking
+    /// an uncontended global need not lock the queue.
+    #[cfg(feature = "async")]
+    waiting: AtomicBool,
+    #[cfg(feature = "async")]
+    queue: Mutex<Vec<Waker>>,
+}
+
+impl Wakers {
+    pub(crate) const fn new() -> Self {
+        Wakers {
+            #[cfg(feature = "async")]
+            waiting: AtomicBool::new(false),
+            #[cfg(feature = "async")]
+            queue: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Arrange for `waker` to be woken the next time the
+    /// global is unlocked. The caller must try the lock again
+    /// after registering, since the global may have been
+    /// unlocked in the meantime.
+    #[cfg(feature = "async")]
+    pub(crate) fn register(&self, waker: &Waker) {
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        if !queue.iter().any(|w| w.will_wake(waker)) {
+            queue.push(waker.clone());
+        }
+        self.waiting.store(true, Ordering::SeqCst);
+        drop(queue);
+        // Pairs with the fence in `wake()`: either the waker
+        // sees `waiting` or the retried lock sees the unlock.
+        fence(Ordering::SeqCst);
+    }
+
+    /// Wake every registered task. Called just after the
+    /// global is unlocked.
+    fn wake(&self) {
+        #[cfg(feature = "async")]
+        {
+            fence(Ordering::SeqCst);
+            if self.waiting.load(Ordering::Relaxed) && self.waiting.swap(false, Ordering::SeqCst) {
+                let wakers = core::mem::take(
+                    &mut *self.queue.lock().unwrap_or_else(PoisonError::into_inner),
+                );
+                for w in wakers {
+                    w.wake();
+                }
+            }
+        }
+    }
+}
+
+/// Wakes the waiting tasks when dropped. Must be dropped after
+/// the mutex guard it accompanies.
+pub(crate) struct WakeOnDrop<'a>(&'a Wakers);
+
+impl<'a> WakeOnDrop<'a> {
+    pub(crate) fn new(wakers: &'a Wakers) -> Self {
+        WakeOnDrop(wakers)
+    }
+}
+
+impl Drop for WakeOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.wake();
+    }
+}
@@ -0,0 +1,17 @@
+//! Check that the borrow checker keeps references to a
+//! global's value from outliving the guard that holds its
+//! lock, that guards stay on the thread that locked them,
+//! and that the results of fallible fetches are not silently
+//! dropped.
+//!
+//! The expected compiler output is in `tests/ui`. It mentions
+//! the standard mutex, so these tests are only run with the
+//! default lock backend.
+
+#![cfg(all(feature = "std", not(feature = "parking_lot")))]
+
+#[test]
+fn guards_do_not_escape() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,96 @@
+//! Check that exchanging values with a global from several
+//! threads at once neither loses nor duplicates any value,
+//! and that flushing a global loses no concurrent update.
+
+#![cfg(feature = "std")]
+
+use std::collections::HashMap;
+use std::thread;
+
+use ugly_global::*;
+
+const THREADS: u32 = 8;
+const ROUNDS: u32 = 10_000;
+
+/// Have each thread swap unique values into `g` with
+/// `exchange`, then check that every value put in came out
+/// exactly once, counting the one left behind.
+fn check_exchange(g: &'static Global<u32>, exchange: fn(&Global<u32>, u32) -> u32) {
+    let workers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            thread::spawn(move || {
+                (0..ROUNDS)
+                    .map(|i| exchange(g, t * ROUNDS + i + 1))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut seen: Vec<u32> = workers
+        .into_iter()
+        .flat_map(|w| w.join().unwrap())
+        .collect();
+    seen.push(g.get_copy());
+    seen.sort_unstable();
+    let expected: Vec<u32> = (0..=THREADS * ROUNDS).collect();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn concurrent_replace() {
+    global_vars! {
+        X: u32 = 0;
+    }
+    check_exchange(&X, Global::replace);
+}
+
+#[test]
+fn concurrent_swap_value() {
+    global_vars! {
+        X: u32 = 0;
+    }
+    check_exchange(&X, Global::swap_value);
+}
+
+#[test]
+fn concurrent_mixed() {
+    global_vars! {
+        X: u32 = 0;
+    }
+    check_exchange(&X, |g, v| {
+        if v % 2 == 0 {
+            g.replace(v)
+        } else {
+            g.swap_value(v)
+        }
+    });
+}
+
+#[test]
+fn concurrent_flush() {
+    global_vars! {
+        HITS: HashMap<u32, u32> = HashMap::new();
+    }
+    let workers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            thread::spawn(move || {
+                for _ in 0..ROUNDS {
+                    *HITS.fetch().entry(t).or_default() += 1;
+                }
+            })
+        })
+        .collect();
+    let mut totals: HashMap<u32, u32> = HashMap::new();
+    while !workers.iter().all(|w| w.is_finished()) {
+        for (k, v) in HITS.swap_with_default() {
+            *totals.entry(k).or_default() += v;
+        }
+    }
+    for w in workers {
+        w.join().unwrap();
+    }
+    for (k, v) in HITS.swap_with_default() {
+        *totals.entry(k).or_default() += v;
+    }
+    assert_eq!(totals.len(), THREADS as usize);
+    assert!(totals.values().all(|&v| v == ROUNDS));
+}
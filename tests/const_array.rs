@@ -0,0 +1,44 @@
+//! Check that `Global::new()` stays usable in `const`
+//! contexts, in particular to fill arrays of globals.
+
+use ugly_global::*;
+
+#[derive(Debug, PartialEq)]
+struct Conn {
+    port: u16,
+}
+
+static POOL: [Global<Conn>; 16] = [const { Global::new() }; 16];
+
+static NAMED: [Global<usize>; 2] = [Global::new_named("FIRST"), Global::new_named("SECOND")];
+
+static WRAPPED: GlobalArray<Conn, 16> = GlobalArray::from_array([const { Global::new() }; 16]);
+
+#[test]
+fn const_array_of_globals() {
+    for (i, g) in POOL.iter().enumerate().step_by(2) {
+        g.init(Conn {
+            port: 8000 + i as u16,
+        });
+    }
+    assert_eq!(*POOL[4].fetch(), Conn { port: 8004 });
+    assert!(POOL[4].is_initialized());
+    assert!(!POOL[5].is_initialized());
+    assert_eq!(POOL.iter().filter(|g| g.is_initialized()).count(), 8);
+}
+
+#[test]
+fn named_array_of_globals() {
+    NAMED[0].init(1);
+    assert_eq!(NAMED[0].name(), Some("FIRST"));
+    assert_eq!(NAMED[1].name(), Some("SECOND"));
+    assert_eq!(NAMED[0].get_copy(), 1);
+}
+
+#[test]
+fn wrapped_array_of_globals() {
+    assert_eq!(WRAPPED.len(), 16);
+    WRAPPED.init(15, Conn { port: 1 });
+    assert_eq!(WRAPPED.fetch(15).port, 1);
+    assert!(WRAPPED.try_fetch(0).is_none());
+}
@@ -0,0 +1,140 @@
+//! Check that `global_vars!` accepts a variety of
+//! real-world types and a mixture of kinds of global, and
+//! that it names each global after its declaration.
+
+#![cfg(feature = "std")]
+
+use std::collections::{BTreeMap, HashMap};
+
+use ugly_global::*;
+
+trait Plugin: Send {
+    fn name(&self) -> &'static str;
+}
+
+struct Echo;
+
+impl Plugin for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+}
+
+global_vars! {
+    CACHE: HashMap<String, Vec<u8>>;
+    ORDERED: BTreeMap<u32, Option<String>>;
+    PAIRS: Vec<(u32, String)>;
+    TUPLE: (u8, (u16, [u32; 4]));
+    PLUGIN: Option<Box<dyn Plugin + Send>>;
+    HANDLER: Box<dyn Fn(u32) -> u32 + Send + Sync>;
+    NESTED: HashMap<String, BTreeMap<u32, Vec<Option<Box<dyn Plugin>>>>>;
+    pub LAZY_MAP: BTreeMap<u32, String> = BTreeMap::new();
+    SPACELESS: Vec<Vec<u8>>= vec![vec![1]];
+    rwsync RW_MAP: HashMap<u32, Vec<(u8, u8)>>;
+    frozen FROZEN_MAP: BTreeMap<&'static str, Vec<u32>>;
+    log_level: String = "info".to_string();
+    mod plugins {
+        pub REGISTERED: Vec<Box<dyn Plugin>> = Vec::new();
+        pub(crate) BY_ID: HashMap<u32, Box<dyn Plugin>>;
+    }
+    local LOCAL_MAP: HashMap<&'static str, Box<dyn Plugin>>;
+}
+
+#[test]
+fn collections() {
+    CACHE.init(HashMap::new());
+    CACHE.fetch().insert("k".to_string(), vec![1]);
+    assert_eq!(CACHE.fetch()["k"], [1]);
+
+    ORDERED.init(BTreeMap::new());
+    ORDERED.fetch().insert(1, None);
+    assert_eq!(ORDERED.fetch().len(), 1);
+
+    PAIRS.init(vec![(1, "one".to_string())]);
+    assert_eq!(PAIRS.fetch()[0].1, "one");
+
+    TUPLE.init((1, (2, [3; 4])));
+    assert_eq!((TUPLE.fetch().1).1[3], 3);
+
+    LAZY_MAP.fetch().insert(1, "one".to_string());
+    assert_eq!(LAZY_MAP.fetch().len(), 1);
+    assert_eq!(SPACELESS.fetch()[0], [1]);
+
+    RW_MAP.init(HashMap::new());
+    RW_MAP.write().insert(1, vec![(2, 3)]);
+    assert_eq!(RW_MAP.read()[&1], [(2, 3)]);
+
+    assert_eq!(*log_level.fetch(), "info");
+
+    FROZEN_MAP.init(BTreeMap::new());
+    assert!(FROZEN_MAP.get().is_empty());
+}
+
+#[test]
+fn trait_objects() {
+    PLUGIN.init(Some(Box::new(Echo)));
+    assert_eq!(PLUGIN.fetch().as_ref().unwrap().name(), "echo");
+
+    HANDLER.init(Box::new(|x| x + 1));
+    assert_eq!((*HANDLER.fetch())(1), 2);
+
+    NESTED.init(HashMap::new());
+    assert!(NESTED.fetch().is_empty());
+
+    plugins::REGISTERED.fetch().push(Box::new(Echo));
+    assert_eq!(plugins::REGISTERED.fetch()[0].name(), "echo");
+    plugins::BY_ID.init(HashMap::new());
+    assert!(plugins::BY_ID.fetch().is_empty());
+
+    LOCAL_MAP.init(HashMap::new());
+    LOCAL_MAP.fetch().insert("echo", Box::new(Echo));
+    assert_eq!(LOCAL_MAP.fetch()["echo"].name(), "echo");
+}
+
+#[test]
+fn mixed_backends() {
+    global_vars! {
+        sync CONFIG: HashMap<String, String>;
+        atomic REQUESTS: u64;
+        pub sync LIMITS: Vec<u32> = vec![1, 2];
+        rwsync ROUTES: Vec<String>;
+        sync: bool;
+    }
+
+    CONFIG.init(HashMap::new());
+    CONFIG
+        .fetch()
+        .insert("mode".to_string(), "fast".to_string());
+    REQUESTS.init(0);
+    REQUESTS.fetch_add(2);
+    LIMITS.fetch().push(3);
+    ROUTES.init(Vec::new());
+    sync.init(true);
+
+    assert_eq!(CONFIG.fetch()["mode"], "fast");
+    assert_eq!(REQUESTS.load(), 2);
+    assert_eq!(*LIMITS.fetch(), [1, 2, 3]);
+    assert!(ROUTES.read().is_empty());
+    assert!(sync.get_copy());
+}
+
+#[test]
+fn double_init_names_global() {
+    global_vars! {
+        SEEDED: u64;
+    }
+    SEEDED.init(1);
+    let result = std::panic::catch_unwind(|| SEEDED.init(2));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(message, "re-initialization of global `SEEDED`");
+}
+
+#[test]
+fn self_referential_initializer_panics() {
+    global_vars! {
+        S: usize = *S.fetch() + 1;
+    }
+    let result = std::panic::catch_unwind(|| S.get_copy());
+    let message = *result.unwrap_err().downcast::<&str>().unwrap();
+    assert_eq!(message, "re-entrant fetch on already-locked global");
+}
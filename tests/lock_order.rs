@@ -0,0 +1,146 @@
+//! Check that, with the `lock_order` feature, locking
+//! globals in inconsistent orders panics in debug builds.
+
+#![cfg(all(feature = "lock_order", debug_assertions))]
+
+use std::panic;
+use std::time::Duration;
+
+use ugly_global::*;
+
+#[test]
+fn consistent_order() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    for _ in 0..2 {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    }
+    // Either may still be locked alone.
+    drop(B.fetch());
+    drop(A.fetch());
+}
+
+#[test]
+fn inverted_order() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    }
+    let result = panic::catch_unwind(|| {
+        let _b = B.fetch();
+        let _a = A.fetch();
+    });
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        message,
+        "lock order violation: global `A` locked while holding `B`, \
+         which has been locked while holding it",
+    );
+    // The failed fetch did not lock `A`.
+    assert!(A.try_fetch_now().is_some());
+}
+
+#[test]
+fn inverted_order_across_threads() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    std::thread::spawn(|| {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    })
+    .join()
+    .unwrap();
+    let result = panic::catch_unwind(|| {
+        let _b = B.fetch();
+        let _a = A.fetch();
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn inverted_order_through_third_global() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+        C: usize = 0;
+    }
+    {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    }
+    {
+        let _b = B.fetch();
+        let _c = C.fetch();
+    }
+    let result = panic::catch_unwind(|| {
+        let _c = C.fetch();
+        let _a = A.fetch();
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn inverted_order_without_blocking() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    {
+        let _a = A.fetch_timeout(Duration::from_millis(10)).unwrap();
+        let _b = B.try_fetch_now().unwrap();
+    }
+    let result = panic::catch_unwind(|| {
+        let _b = B.fetch();
+        assert!(A.try_fetch_now().is_some());
+    });
+    assert!(result.is_ok());
+    let result = panic::catch_unwind(|| {
+        let _b = B.fetch();
+        assert!(A.fetch_timeout(Duration::from_millis(10)).is_some());
+    });
+    assert!(result.is_ok());
+    // A blocking lock in the inverted order is still caught.
+    let result = panic::catch_unwind(|| {
+        let _b = B.fetch();
+        let _a = A.fetch();
+    });
+    assert!(result.is_err());
+}
+
+#[test]
+fn inspect_never_panics() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    }
+    let _b = B.fetch();
+    assert_eq!(A.inspect(|a| a.copied()), Some(0));
+    assert_eq!(format!("{:?}", A), r#"Global { name: "A", value: 0 }"#);
+}
+
+#[test]
+fn get_cloned_or_never_panics() {
+    global_vars! {
+        A: usize = 0;
+        B: usize = 0;
+    }
+    {
+        let _a = A.fetch();
+        let _b = B.fetch();
+    }
+    let _b = B.fetch();
+    assert_eq!(A.get_cloned_or(1), 0);
+}
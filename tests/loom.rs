@@ -0,0 +1,110 @@
+//! Exhaustively check the interleavings of concurrent access
+//! to a `Global`, using `loom`. Under `--cfg loom` the
+//! global's mutex, condition variable, once-cell and
+//! re-entrance check are all `loom` models, so the races
+//! between initialization and first access are explored as
+//! well as those between lockers. The debug-only and
+//! feature-gated atomics are not modelled. Run with
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+
+use ugly_global::*;
+
+#[test]
+fn racing_init() {
+    loom::model(|| {
+        let g = Arc::new(Global::new());
+        let racers: Vec<_> = (1..=2)
+            .map(|i| {
+                let g = g.clone();
+                thread::spawn(move || g.try_init(i).is_ok())
+            })
+            .collect();
+        let won: Vec<bool> = racers.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(won.iter().filter(|&&w| w).count(), 1);
+        let winner = if won[0] { 1 } else { 2 };
+        assert_eq!(g.get_copy(), winner);
+    });
+}
+
+#[test]
+fn init_races_first_access() {
+    loom::model(|| {
+        let g = Arc::new(Global::new());
+        let seeder = {
+            let g = g.clone();
+            thread::spawn(move || g.set_if_uninitialized(1))
+        };
+        let v = *g.get_or_init(|| 2);
+        assert_eq!(seeder.join().unwrap(), v == 1);
+        assert_eq!(g.get_copy(), v);
+    });
+}
+
+#[test]
+fn fetch_and_set() {
+    loom::model(|| {
+        let g = Arc::new(Global::from_value(1));
+        let setter = {
+            let g = g.clone();
+            thread::spawn(move || g.set(2))
+        };
+        *g.fetch() += 1;
+        setter.join().unwrap();
+        // Either the increment or the set came last.
+        let v = g.get_copy();
+        assert!(v == 2 || v == 3, "lost update: {}", v);
+    });
+}
+
+#[test]
+fn racing_replace() {
+    loom::model(|| {
+        let g = Arc::new(Global::from_value(0));
+        let other = {
+            let g = g.clone();
+            thread::spawn(move || g.replace(1))
+        };
+        let mut seen = vec![g.replace(2), other.join().unwrap(), g.get_copy()];
+        seen.sort_unstable();
+        assert_eq!(seen, [0, 1, 2]);
+    });
+}
+
+#[test]
+fn lazy_init_runs_once() {
+    loom::model(|| {
+        let g: Arc<Global<Vec<u8>>> = Arc::new(Global::new_lazy(|| vec![0]));
+        let other = {
+            let g = g.clone();
+            thread::spawn(move || g.fetch().push(1))
+        };
+        g.fetch().push(2);
+        other.join().unwrap();
+        // A second run of the initializer would lose a push.
+        assert_eq!(g.fetch().len(), 3);
+    });
+}
+
+#[test]
+fn wait_for_init() {
+    loom::model(|| {
+        let g = Arc::new(Global::new());
+        let waiter = {
+            let g = g.clone();
+            thread::spawn(move || {
+                g.wait_until_initialized();
+                g.get_copy()
+            })
+        };
+        g.init(7);
+        assert_eq!(waiter.join().unwrap(), 7);
+    });
+}
@@ -0,0 +1,88 @@
+//! Check that `on_change()` callbacks see every change made
+//! through the crate's mutating methods, and nothing else.
+
+#![cfg(feature = "std")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ugly_global::*;
+
+/// Record each value that `g` is changed to.
+fn watch<T: Clone + Send + 'static>(g: &Global<T>) -> Arc<Mutex<Vec<T>>> {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    g.on_change(move |v| log.lock().unwrap().push(v.clone()));
+    seen
+}
+
+#[test]
+fn take() {
+    global_vars! {
+        X: Vec<u8> = vec![1];
+    }
+    let seen = watch(&X);
+    assert_eq!(X.take(), [1]);
+    assert_eq!(*seen.lock().unwrap(), [vec![]]);
+}
+
+#[test]
+fn swap_with_default() {
+    global_vars! {
+        X: u32 = 1;
+    }
+    let seen = watch(&X);
+    assert_eq!(X.swap_with_default(), 1);
+    assert_eq!(*seen.lock().unwrap(), [0]);
+}
+
+#[test]
+fn swap() {
+    global_vars! {
+        A: u32 = 1;
+        B: u32 = 2;
+    }
+    let seen_a = watch(&A);
+    let seen_b = watch(&B);
+    A.swap(&B);
+    assert_eq!(*seen_a.lock().unwrap(), [2]);
+    assert_eq!(*seen_b.lock().unwrap(), [1]);
+}
+
+#[test]
+fn try_with_timeout() {
+    global_vars! {
+        X: u32 = 1;
+    }
+    let seen = watch(&X);
+    X.try_with_timeout(Duration::from_millis(10), |x| *x += 1)
+        .unwrap();
+    assert_eq!(*seen.lock().unwrap(), [2]);
+}
+
+#[test]
+fn scoped_override_and_restore() {
+    global_vars! {
+        X: u32 = 1;
+    }
+    let seen = watch(&X);
+    drop(X.scoped_override(2));
+    assert_eq!(*seen.lock().unwrap(), [2, 1]);
+}
+
+#[test]
+fn with() {
+    global_vars! {
+        X: u32 = 1;
+    }
+    let seen = watch(&X);
+    assert_eq!(
+        X.with(|x| {
+            *x += 1;
+            *x * 10
+        }),
+        20,
+    );
+    assert_eq!(X.with(|x| *x), 2);
+    assert_eq!(*seen.lock().unwrap(), [2, 2]);
+}
@@ -0,0 +1,83 @@
+//! Check that tasks waiting in `poll_fetch()` or
+//! `fetch_async()` are always woken when the global they wait
+//! for is unlocked, however the unlocking thread got the lock.
+
+#![cfg(feature = "async")]
+
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use ugly_global::*;
+
+global_vars! {
+    COUNT: usize = 0;
+}
+
+/// Waker that unparks the thread polling the task.
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+const ROUNDS: usize = 10_000;
+
+#[test]
+fn no_lost_wakeups() {
+    let blocking = thread::spawn(|| {
+        for i in 0..ROUNDS {
+            // Unlock by each of the guarded and unguarded paths.
+            if i % 2 == 0 {
+                *COUNT.fetch() += 1;
+            } else {
+                COUNT.with_poisoned(|c| match c {
+                    Ok(c) | Err(c) => *c += 1,
+                });
+            }
+        }
+    });
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    for _ in 0..ROUNDS {
+        loop {
+            match COUNT.poll_fetch(&mut cx) {
+                Poll::Ready(mut c) => {
+                    *c += 1;
+                    break;
+                }
+                // A lost wakeup would park here forever.
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    blocking.join().unwrap();
+    assert_eq!(*COUNT.fetch(), 2 * ROUNDS);
+}
+
+#[test]
+fn fetch_async_contended() {
+    global_vars! {
+        TOTAL: usize = 0;
+    }
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                futures::executor::block_on(async {
+                    for _ in 0..ROUNDS {
+                        *TOTAL.fetch_async().await += 1;
+                    }
+                })
+            })
+        })
+        .collect();
+    for w in workers {
+        w.join().unwrap();
+    }
+    assert_eq!(*TOTAL.fetch(), 4 * ROUNDS);
+}
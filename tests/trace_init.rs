@@ -0,0 +1,80 @@
+//! Check that, with the `trace_init` feature, each
+//! initialization of a global is logged with the name of the
+//! global and the caller, however it is initialized.
+
+#![cfg(feature = "trace_init")]
+
+use std::sync::Mutex;
+
+use log::{Level, Log, Metadata, Record};
+use ugly_global::*;
+
+/// Logger that keeps the messages it is given.
+struct Recorder(Mutex<Vec<String>>);
+
+impl Log for Recorder {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Debug
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+global_vars! {
+    FIRST: usize;
+    SECOND: String;
+    TRIED: usize;
+    SEEDED: usize;
+    ON_ACCESS: usize;
+    SET: usize;
+    LAZY: usize = 6;
+}
+
+static NAMELESS: Global<usize> = Global::new();
+
+#[test]
+fn init_is_logged() {
+    log::set_logger(&RECORDER).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let line = line!() + 1;
+    init!(FIRST = 1, SECOND = "two".to_string());
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| NAMELESS.init(3))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(TRIED.try_init(4), Ok(()));
+    // Failures are not initializations.
+    assert_eq!(TRIED.try_init(4), Err(4));
+    assert!(SEEDED.set_if_uninitialized(5));
+    assert!(!SEEDED.set_if_uninitialized(5));
+    drop(ON_ACCESS.get_or_init(|| 6));
+    drop(ON_ACCESS.get_or_init(|| 6));
+    SET.set(7);
+    SET.set(8);
+    drop(LAZY.fetch());
+    drop(LAZY.fetch());
+
+    let messages = RECORDER.0.lock().unwrap();
+    assert_eq!(messages.len(), 8);
+    assert!(messages[0].starts_with("initialized global `FIRST` on thread init_is_logged ("));
+    assert!(messages[0].ends_with(&format!("at tests/trace_init.rs:{}:5", line)));
+    assert!(messages[1].starts_with("initialized global `SECOND`"));
+    assert!(messages[2].starts_with("initialized global `<unnamed>` on thread worker ("));
+    for (message, name) in messages[3..]
+        .iter()
+        .zip(["TRIED", "SEEDED", "ON_ACCESS", "SET", "LAZY"])
+    {
+        assert!(message.starts_with(&format!("initialized global `{}`", name)));
+        assert!(message.contains("at tests/trace_init.rs:"));
+    }
+}
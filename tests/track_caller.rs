@@ -0,0 +1,60 @@
+//! Check that panics report the location of the caller
+//! rather than somewhere inside this crate.
+
+#![cfg(feature = "std")]
+
+use std::panic::{self, UnwindSafe};
+use std::sync::Mutex;
+
+use ugly_global::*;
+
+global_vars! {
+    UNINIT: usize;
+    TWICE: usize;
+    HELD: usize;
+}
+
+static LINE: Mutex<Option<u32>> = Mutex::new(None);
+
+/// The line at which `f` panicked, as reported to the panic
+/// hook.
+fn panic_line<F: FnOnce() + UnwindSafe>(f: F) -> u32 {
+    let result = panic::catch_unwind(f);
+    assert!(result.is_err());
+    LINE.lock().unwrap().take().unwrap()
+}
+
+#[test]
+fn panic_locations() {
+    panic::set_hook(Box::new(|info| {
+        *LINE.lock().unwrap() = info.location().map(|l| l.line());
+    }));
+
+    let line = line!() + 1;
+    assert_eq!(panic_line(|| drop(UNINIT.fetch())), line);
+
+    let line = line!() + 3;
+    assert_eq!(
+        panic_line(|| {
+            UNINIT.get_copy();
+        }),
+        line
+    );
+
+    TWICE.init(0);
+    let line = line!() + 1;
+    assert_eq!(panic_line(|| init!(TWICE = 1)), line);
+
+    HELD.init(0);
+    let line = line!() + 4;
+    assert_eq!(
+        panic_line(|| {
+            fetch!(held = HELD);
+            let _again = HELD.fetch();
+            *held += 1;
+        }),
+        line
+    );
+
+    drop(panic::take_hook());
+}
@@ -0,0 +1,11 @@
+use ugly_global::*;
+
+global_vars! {
+    X: usize;
+}
+
+fn main() {
+    init!(X = 0);
+    let x = X.fetch();
+    std::thread::spawn(move || drop(x));
+}
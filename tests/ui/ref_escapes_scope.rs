@@ -0,0 +1,11 @@
+use ugly_global::*;
+
+global_vars! {
+    X: usize;
+}
+
+fn main() {
+    init!(X = 0);
+    let r = scope!(x = X { &mut *x });
+    *r += 1;
+}
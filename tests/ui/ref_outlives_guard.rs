@@ -0,0 +1,15 @@
+use ugly_global::*;
+
+global_vars! {
+    X: usize;
+}
+
+fn main() {
+    init!(X = 0);
+    let r;
+    {
+        let mut x = X.fetch();
+        r = &mut *x;
+    }
+    *r += 1;
+}
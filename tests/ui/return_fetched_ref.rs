@@ -0,0 +1,15 @@
+use ugly_global::*;
+
+global_vars! {
+    X: usize;
+}
+
+fn leak() -> &'static mut usize {
+    fetch!(x = X);
+    &mut *x
+}
+
+fn main() {
+    init!(X = 0);
+    *leak() += 1;
+}
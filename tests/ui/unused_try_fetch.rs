@@ -0,0 +1,13 @@
+#![deny(unused_must_use)]
+
+use ugly_global::*;
+
+global_vars! {
+    X: usize;
+}
+
+fn main() {
+    X.try_fetch();
+    X.checked_fetch();
+    X.try_init(0);
+}